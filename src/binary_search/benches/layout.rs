@@ -0,0 +1,84 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compares lookups in `EytzingerArray` and `SearchTree` with bisection on
+//! the plain sorted slice, from cache-resident sizes up to main memory.
+//!
+//! Run with `cargo bench --bench layout`, optionally passing the largest
+//! size as a power of two (default 26, 256 MiB of `u32`).
+
+extern crate binary_search;
+
+use binary_search::{binary_search_first_gte, EytzingerArray, SearchTree};
+use std::env;
+use std::hint::black_box;
+use std::time::Instant;
+
+const QUERIES: usize = 1 << 20;
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+fn time<F: FnMut(u32) -> Option<u32>>(name: &str, queries: &[u32], mut lookup: F) {
+    let start = Instant::now();
+    let mut checksum = 0u64;
+    for &q in queries {
+        checksum += lookup(q).unwrap_or(0) as u64;
+    }
+    let elapsed = start.elapsed();
+
+    black_box(checksum);
+    println!(
+        "  {:<16} {:>8.1} ns/query",
+        name,
+        elapsed.as_secs_f64() * 1e9 / queries.len() as f64
+    );
+}
+
+fn main() {
+    let max_log = env::args()
+        .skip(1)
+        .find(|arg| !arg.starts_with('-'))
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(26);
+
+    let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+    for log in (10..max_log + 1).step_by(4) {
+        let n = 1usize << log;
+        let a: Vec<u32> = (0..n as u32).map(|x| x * 3).collect();
+        let queries: Vec<u32> = (0..QUERIES)
+            .map(|_| (rng.next() % (3 * n as u64)) as u32)
+            .collect();
+
+        let eytzinger = EytzingerArray::new(&a);
+        let tree: SearchTree<u32, 16> = SearchTree::new(&a);
+
+        println!("n = 2^{} ({} KiB)", log, n * 4 / 1024);
+        time("binary search", &queries, |q| {
+            binary_search_first_gte(&a, q).map(|i| a[i])
+        });
+        time("eytzinger", &queries, |q| {
+            eytzinger.lower_bound(&q).cloned()
+        });
+        time("search tree", &queries, |q| tree.lower_bound(&q).cloned());
+    }
+}
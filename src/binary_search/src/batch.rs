@@ -0,0 +1,265 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Answering many lookups at once.
+
+use super::{galloping_search, lower_bound};
+
+/// Search each of `needles` in the sorted `haystack`
+///
+/// Each search gallops forward from where the previous one ended, so sorted
+/// needles cost O(m log(n / m)) comparisons in total instead of O(m log n).
+/// A needle smaller than the one before it restarts from the front, so the
+/// results are correct for needles in any order.
+///
+/// # Arguments
+/// * `haystack` - A sorted slice that hold values
+/// * `needles` - The values to be searched, ideally sorted
+///
+/// # Return
+/// For every needle, `Ok` with the index of the first equal element or `Err`
+/// with the index where it could be inserted, like `slice::binary_search`
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::batch_search;
+///
+/// let table = vec![10, 20, 20, 30, 40];
+/// assert_eq!(batch_search(&table, &[5, 20, 35, 40]), vec![Err(0), Ok(1), Err(4), Ok(4)]);
+/// ```
+pub fn batch_search<T: PartialOrd>(haystack: &[T], needles: &[T]) -> Vec<Result<usize, usize>> {
+    let mut results = Vec::with_capacity(needles.len());
+    let mut start = 0;
+
+    for (i, v) in needles.iter().enumerate() {
+        if i > 0 && *v < needles[i - 1] {
+            start = 0;
+        }
+
+        let r = match galloping_search(&haystack[start..], v, 0) {
+            Ok(j) => Ok(start + j),
+            Err(j) => Err(start + j),
+        };
+        start = match r {
+            Ok(j) | Err(j) => j,
+        };
+        results.push(r);
+    }
+
+    results
+}
+
+/// One list of a `FractionalCascading`, merged with every second element of
+/// the next level.
+#[derive(Debug, Clone)]
+struct Level<T> {
+    merged: Vec<T>,
+    // for each merged element, and one past the end: its lower bound in the
+    // level's own list and in the next level's merged list
+    own: Vec<usize>,
+    next: Vec<usize>,
+}
+
+/// Sorted lists prepared to look up a value in all of them in O(log n + k)
+/// instead of O(k log n).
+///
+/// Each list is merged with every second element of the merged list after
+/// it, and every element remembers where it falls in its own list and in the
+/// next merged list. One binary search in the first merged list then leads to
+/// the answer in every list, correcting by at most one step per level.
+///
+/// reference:
+///     https://en.wikipedia.org/wiki/Fractional_cascading
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::FractionalCascading;
+///
+/// let lists = vec![vec![1, 5, 9], vec![2, 4, 6, 8], vec![5, 7]];
+/// let fc = FractionalCascading::new(&lists);
+/// assert_eq!(fc.lower_bounds(&5), vec![1, 2, 0]);
+/// assert_eq!(fc.search(&5), vec![Ok(1), Err(2), Ok(0)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FractionalCascading<T> {
+    lists: Vec<Vec<T>>,
+    levels: Vec<Level<T>>,
+}
+
+impl<T: PartialOrd + Copy> FractionalCascading<T> {
+    /// Builds the structure from sorted lists.
+    pub fn new<L: AsRef<[T]>>(lists: &[L]) -> FractionalCascading<T> {
+        let lists: Vec<Vec<T>> = lists.iter().map(|l| l.as_ref().to_vec()).collect();
+        debug_assert!(
+            lists.iter().all(|l| l.windows(2).all(|w| w[0] <= w[1])),
+            "not sorted"
+        );
+
+        let mut levels: Vec<Level<T>> = Vec::with_capacity(lists.len());
+        for list in lists.iter().rev() {
+            let promoted: Vec<T> = match levels.last() {
+                Some(next) => next.merged.iter().skip(1).step_by(2).cloned().collect(),
+                None => Vec::new(),
+            };
+            let merged = merge(list, &promoted);
+
+            let mut own: Vec<usize> = merged.iter().map(|x| lower_bound(list, x)).collect();
+            own.push(list.len());
+            let next = match levels.last() {
+                Some(next) => {
+                    let mut bridges: Vec<usize> = merged
+                        .iter()
+                        .map(|x| lower_bound(&next.merged, x))
+                        .collect();
+                    bridges.push(next.merged.len());
+                    bridges
+                }
+                None => vec![0; merged.len() + 1],
+            };
+
+            levels.push(Level { merged, own, next });
+        }
+        levels.reverse();
+
+        FractionalCascading { lists, levels }
+    }
+
+    /// Number of lists.
+    pub fn len(&self) -> usize {
+        self.lists.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lists.is_empty()
+    }
+
+    /// Returns `lower_bound(list, v)` for every list.
+    pub fn lower_bounds(&self, v: &T) -> Vec<usize> {
+        let mut bounds = Vec::with_capacity(self.levels.len());
+        if self.levels.is_empty() {
+            return bounds;
+        }
+
+        let mut p = lower_bound(&self.levels[0].merged, v);
+        for (i, level) in self.levels.iter().enumerate() {
+            bounds.push(level.own[p]);
+
+            if let Some(next) = self.levels.get(i + 1) {
+                // lower_bound(next, merged[p]) overshoots by at most the one
+                // element of `next` that was not promoted
+                p = level.next[p];
+                if p > 0 && next.merged[p - 1] >= *v {
+                    p -= 1;
+                }
+            }
+        }
+
+        bounds
+    }
+
+    /// Searches `v` in every list, with results like `slice::binary_search`.
+    pub fn search(&self, v: &T) -> Vec<Result<usize, usize>> {
+        self.lower_bounds(v)
+            .into_iter()
+            .zip(&self.lists)
+            .map(|(i, list)| {
+                if i < list.len() && list[i] == *v {
+                    Ok(i)
+                } else {
+                    Err(i)
+                }
+            })
+            .collect()
+    }
+}
+
+fn merge<T: PartialOrd + Copy>(a: &[T], b: &[T]) -> Vec<T> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() && j < b.len() {
+        if b[j] < a[i] {
+            out.push(b[j]);
+            j += 1;
+        } else {
+            out.push(a[i]);
+            i += 1;
+        }
+    }
+    out.extend_from_slice(&a[i..]);
+    out.extend_from_slice(&b[j..]);
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{sorted_inputs, XorShift};
+
+    fn expected(a: &[u64], v: u64) -> Result<usize, usize> {
+        let i = lower_bound(a, &v);
+        if i < a.len() && a[i] == v {
+            Ok(i)
+        } else {
+            Err(i)
+        }
+    }
+
+    #[test]
+    fn test_batch_search() {
+        let mut rng = XorShift(0xba7c);
+
+        for haystack in sorted_inputs() {
+            let max = haystack.last().cloned().unwrap_or(0) + 2;
+            let mut needles: Vec<u64> = (0..rng.below(40)).map(|_| rng.below(max)).collect();
+
+            let results = batch_search(&haystack, &needles);
+            let e: Vec<_> = needles.iter().map(|&v| expected(&haystack, v)).collect();
+            assert_eq!(results, e);
+
+            needles.sort();
+            let results = batch_search(&haystack, &needles);
+            let e: Vec<_> = needles.iter().map(|&v| expected(&haystack, v)).collect();
+            assert_eq!(results, e);
+        }
+    }
+
+    #[test]
+    fn test_fractional_cascading() {
+        let empty: FractionalCascading<u64> = FractionalCascading::new::<Vec<u64>>(&[]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.lower_bounds(&1), vec![]);
+
+        let mut rng = XorShift(0xfc);
+        let inputs = sorted_inputs();
+        for _ in 0..100 {
+            let k = rng.below(8) as usize + 1;
+            let lists: Vec<Vec<u64>> = (0..k)
+                .map(|_| inputs[rng.below(inputs.len() as u64) as usize].clone())
+                .collect();
+            let fc = FractionalCascading::new(&lists);
+            assert_eq!(fc.len(), k);
+
+            for v in 0..140 {
+                let e: Vec<_> = lists.iter().map(|l| expected(l, v)).collect();
+                assert_eq!(fc.search(&v), e);
+            }
+        }
+    }
+}
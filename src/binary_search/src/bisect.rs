@@ -0,0 +1,488 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Searching over monotone and unimodal functions instead of slices.
+
+/// Integers that `bisect_int` can search over.
+pub trait BisectInt: Copy + Ord {
+    /// The point halfway from `low` to `high`, rounded towards `low`, without
+    /// overflowing for any `low <= high`.
+    fn midpoint(low: Self, high: Self) -> Self;
+
+    /// `self + 1`, only called when it does not overflow.
+    fn succ(self) -> Self;
+}
+
+macro_rules! bisect_int_impl {
+    ($($t:ty, $u:ty);*) => {
+        $(
+            impl BisectInt for $t {
+                #[inline]
+                fn midpoint(low: $t, high: $t) -> $t {
+                    // the distance always fits the unsigned type
+                    let half = (high as $u).wrapping_sub(low as $u) / 2;
+                    low.wrapping_add(half as $t)
+                }
+
+                #[inline]
+                fn succ(self) -> $t {
+                    self + 1
+                }
+            }
+        )*
+    };
+}
+
+bisect_int_impl!(
+    u8, u8; u16, u16; u32, u32; u64, u64; u128, u128; usize, usize;
+    i8, u8; i16, u16; i32, u32; i64, u64; i128, u128; isize, usize
+);
+
+/// Binary search the smallest integer for which a monotone predicate holds
+///
+/// # Arguments
+/// * `lo` - Start of the half-open range `[lo, hi)` to search
+/// * `hi` - End of the range
+/// * `pred` - False up to some point of the range and true from there on
+///
+/// # Return
+/// The first `x` in `[lo, hi)` with `pred(x)`, `hi` if there is none
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::bisect_int;
+///
+/// // integer square root of 10^12
+/// let n = 1_000_000_000_000u64;
+/// let root = bisect_int(0, n, |x| x.checked_mul(x).map_or(true, |sq| sq > n)) - 1;
+/// assert_eq!(root, 1_000_000);
+/// ```
+pub fn bisect_int<T, P>(lo: T, hi: T, mut pred: P) -> T
+where
+    T: BisectInt,
+    P: FnMut(T) -> bool,
+{
+    let mut low = lo;
+    let mut high = hi;
+
+    // invariant: pred fails before `low` and holds from `high` on
+    while low < high {
+        let mid = T::midpoint(low, high);
+
+        if pred(mid) {
+            high = mid;
+        } else {
+            low = mid.succ();
+        }
+    }
+
+    low
+}
+
+/// When `bisect_f64` and the root finders stop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stop {
+    /// Once the bracket is at most this wide.
+    Tolerance(f64),
+    /// After this many halvings.
+    Iterations(u32),
+}
+
+/// Bisection for the point where a monotone predicate on reals turns true
+///
+/// Also stops once the bracket cannot be halved in `f64` precision.
+///
+/// # Arguments
+/// * `lo` - Start of the bracket, where `pred` is assumed false
+/// * `hi` - End of the bracket, where `pred` is assumed true
+/// * `pred` - False up to some point of the bracket and true from there on
+/// * `stop` - When to stop
+///
+/// # Return
+/// The upper end of the final bracket, a point where `pred` holds
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::{bisect_f64, Stop};
+///
+/// let x = bisect_f64(0.0, 2.0, |x| x * x >= 2.0, Stop::Tolerance(1e-12));
+/// assert!((x - 2f64.sqrt()).abs() < 1e-12);
+/// ```
+pub fn bisect_f64<P>(lo: f64, hi: f64, mut pred: P, stop: Stop) -> f64
+where
+    P: FnMut(f64) -> bool,
+{
+    let mut low = lo;
+    let mut high = hi;
+    let mut iterations = 0;
+
+    loop {
+        match stop {
+            Stop::Tolerance(tol) if high - low <= tol => break,
+            Stop::Iterations(max) if iterations >= max => break,
+            _ => {}
+        }
+
+        let mid = low + (high - low) / 2.0;
+        if mid <= low || mid >= high {
+            break;
+        }
+
+        if pred(mid) {
+            high = mid;
+        } else {
+            low = mid;
+        }
+        iterations += 1;
+    }
+
+    high
+}
+
+/// Find a root of `f` between `lo` and `hi`, in either order, by bisection
+///
+/// # Return
+/// A point within the stopping tolerance of a root, `None` if `f(lo)` and
+/// `f(hi)` have the same sign
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::{find_root_bisection, Stop};
+///
+/// let x = find_root_bisection(|x| x * x * x - 8.0, 0.0, 5.0, Stop::Tolerance(1e-10));
+/// assert!((x.unwrap() - 2.0).abs() < 1e-10);
+/// assert_eq!(find_root_bisection(|x| x * x + 1.0, -1.0, 1.0, Stop::Iterations(50)), None);
+/// ```
+pub fn find_root_bisection<F>(mut f: F, lo: f64, hi: f64, stop: Stop) -> Option<f64>
+where
+    F: FnMut(f64) -> f64,
+{
+    let (lo, hi) = if lo <= hi { (lo, hi) } else { (hi, lo) };
+    let (f_lo, f_hi) = (f(lo), f(hi));
+
+    if f_lo == 0.0 {
+        return Some(lo);
+    }
+    if f_hi == 0.0 {
+        return Some(hi);
+    }
+    if (f_lo > 0.0) == (f_hi > 0.0) {
+        return None;
+    }
+
+    // f(x) has the sign of f(hi) from the root on
+    let positive = f_hi > 0.0;
+    Some(bisect_f64(lo, hi, |x| (f(x) >= 0.0) == positive, stop))
+}
+
+// Brent's method gives up after this many steps
+const BRENT_MAX_ITERATIONS: usize = 200;
+
+/// Find a root of `f` in `[lo, hi]` with Brent's method
+///
+/// Combines inverse quadratic interpolation and the secant method with
+/// bisection as a safeguard, converging superlinearly on smooth functions
+/// while never doing worse than bisection by much.
+///
+/// # Return
+/// A point within `tol` of a root, `None` if `f(lo)` and `f(hi)` have the
+/// same sign or the method did not converge
+///
+/// reference:
+///     https://en.wikipedia.org/wiki/Brent%27s_method
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::find_root_brent;
+///
+/// let x = find_root_brent(|x: f64| x.cos() - x, 0.0, 1.0, 1e-12).unwrap();
+/// assert!((x.cos() - x).abs() < 1e-12);
+/// ```
+pub fn find_root_brent<F>(mut f: F, lo: f64, hi: f64, tol: f64) -> Option<f64>
+where
+    F: FnMut(f64) -> f64,
+{
+    let (mut a, mut b) = (lo, hi);
+    let (mut fa, mut fb) = (f(a), f(b));
+
+    if fa == 0.0 {
+        return Some(a);
+    }
+    if (fa > 0.0) == (fb > 0.0) && fb != 0.0 {
+        return None;
+    }
+
+    // b is the best estimate, c the previous one or the other end of the
+    // bracket [b, c], d the last step and e the one before
+    let (mut c, mut fc) = (b, fb);
+    let mut d = b - a;
+    let mut e = d;
+
+    for _ in 0..BRENT_MAX_ITERATIONS {
+        if (fb > 0.0) == (fc > 0.0) {
+            c = a;
+            fc = fa;
+            d = b - a;
+            e = d;
+        }
+        if fc.abs() < fb.abs() {
+            a = b;
+            b = c;
+            c = a;
+            fa = fb;
+            fb = fc;
+            fc = fa;
+        }
+
+        let tol1 = 2.0 * f64::EPSILON * b.abs() + 0.5 * tol;
+        let xm = 0.5 * (c - b);
+        if xm.abs() <= tol1 || fb == 0.0 {
+            return Some(b);
+        }
+
+        if e.abs() >= tol1 && fa.abs() > fb.abs() {
+            let s = fb / fa;
+            let (mut p, mut q) = if a == c {
+                // secant
+                (2.0 * xm * s, 1.0 - s)
+            } else {
+                // inverse quadratic interpolation
+                let q = fa / fc;
+                let r = fb / fc;
+                (
+                    s * (2.0 * xm * q * (q - r) - (b - a) * (r - 1.0)),
+                    (q - 1.0) * (r - 1.0) * (s - 1.0),
+                )
+            };
+            if p > 0.0 {
+                q = -q;
+            }
+            p = p.abs();
+
+            // accept the interpolation only if it stays in the bracket and
+            // shrinks faster than bisection would
+            let min1 = 3.0 * xm * q - (tol1 * q).abs();
+            let min2 = (e * q).abs();
+            if 2.0 * p < min1.min(min2) {
+                e = d;
+                d = p / q;
+            } else {
+                d = xm;
+                e = d;
+            }
+        } else {
+            d = xm;
+            e = d;
+        }
+
+        a = b;
+        fa = fb;
+        b += if d.abs() > tol1 { d } else { tol1.copysign(xm) };
+        fb = f(b);
+    }
+
+    None
+}
+
+/// Ternary search for the minimum of a unimodal function on `[lo, hi]`
+///
+/// Negate `f` to find a maximum.
+///
+/// # Return
+/// A point within `tol` of the minimum
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::ternary_search;
+///
+/// let x = ternary_search(-10.0, 10.0, |x| (x - 3.0) * (x - 3.0), 1e-9);
+/// assert!((x - 3.0).abs() < 1e-9);
+/// ```
+pub fn ternary_search<F>(lo: f64, hi: f64, mut f: F, tol: f64) -> f64
+where
+    F: FnMut(f64) -> f64,
+{
+    let mut low = lo;
+    let mut high = hi;
+
+    while high - low > tol {
+        let third = (high - low) / 3.0;
+        let (m1, m2) = (low + third, high - third);
+        if m1 <= low || m2 >= high {
+            break;
+        }
+
+        if f(m1) < f(m2) {
+            high = m2;
+        } else {
+            low = m1;
+        }
+    }
+
+    low + (high - low) / 2.0
+}
+
+/// Golden-section search for the minimum of a unimodal function on
+/// `[lo, hi]`
+///
+/// Like `ternary_search` but the probes split the bracket in the golden
+/// ratio, so one of them is reused and each step costs one evaluation of `f`
+/// instead of two.
+///
+/// # Return
+/// A point within `tol` of the minimum. Near a smooth minimum `f` is flat to
+/// within rounding over about `sqrt(f64::EPSILON)`, so tighter tolerances
+/// only help for kinks
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::golden_section_search;
+///
+/// let x = golden_section_search(0.0, 3.0, |x: f64| -x.sin(), 1e-9);
+/// assert!((x - std::f64::consts::FRAC_PI_2).abs() < 1e-6);
+/// ```
+pub fn golden_section_search<F>(lo: f64, hi: f64, mut f: F, tol: f64) -> f64
+where
+    F: FnMut(f64) -> f64,
+{
+    // 1 / phi
+    let inv_phi = (5f64.sqrt() - 1.0) / 2.0;
+
+    let mut low = lo;
+    let mut high = hi;
+    let mut m1 = high - (high - low) * inv_phi;
+    let mut m2 = low + (high - low) * inv_phi;
+    let mut f1 = f(m1);
+    let mut f2 = f(m2);
+
+    while high - low > tol {
+        if f1 < f2 {
+            high = m2;
+            m2 = m1;
+            f2 = f1;
+            m1 = high - (high - low) * inv_phi;
+            f1 = f(m1);
+        } else {
+            low = m1;
+            m1 = m2;
+            f1 = f2;
+            m2 = low + (high - low) * inv_phi;
+            f2 = f(m2);
+        }
+
+        if m1 <= low || m2 >= high {
+            break;
+        }
+    }
+
+    low + (high - low) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bisect_int() {
+        assert_eq!(bisect_int(0, 0, |_: i32| true), 0);
+        assert_eq!(bisect_int(0, 10, |_: i32| false), 10);
+        assert_eq!(bisect_int(0, 10, |_: i32| true), 0);
+
+        for t in 0..=u8::MAX {
+            assert_eq!(bisect_int(0, u8::MAX, |x| x >= t), t);
+        }
+        for &t in &[i64::MIN, -5, 0, 7, i64::MAX - 1] {
+            assert_eq!(bisect_int(i64::MIN, i64::MAX, |x| x >= t), t);
+        }
+        assert_eq!(bisect_int(i64::MIN, i64::MAX, |_| false), i64::MAX);
+        assert_eq!(
+            bisect_int(0, usize::MAX, |x| x > usize::MAX / 3),
+            usize::MAX / 3 + 1
+        );
+        assert_eq!(bisect_int(i8::MIN, i8::MAX, |x| x >= -100), -100);
+    }
+
+    #[test]
+    fn test_bisect_f64() {
+        let x = bisect_f64(0.0, 10.0, |x| x >= 3.7, Stop::Tolerance(1e-9));
+        assert!(x >= 3.7 && x - 3.7 <= 1e-9);
+
+        // 10 halvings of a bracket of width 1024
+        let x = bisect_f64(0.0, 1024.0, |x| x >= 100.5, Stop::Iterations(10));
+        assert_eq!(x, 101.0);
+
+        // a zero tolerance stops at adjacent floats
+        let x = bisect_f64(1.0, 2.0, |x| x * x >= 2.0, Stop::Tolerance(0.0));
+        assert!(x * x >= 2.0);
+        assert!(x.next_down() * x.next_down() < 2.0);
+    }
+
+    #[test]
+    fn test_find_root() {
+        let f = |x: f64| (x - 1.0) * (x + 2.0) * (x - 5.0);
+        for &(lo, hi, root) in &[(0.0, 3.0, 1.0), (-10.0, 0.0, -2.0), (3.0, 100.0, 5.0)] {
+            let x = find_root_bisection(f, lo, hi, Stop::Tolerance(1e-12)).unwrap();
+            assert!((x - root).abs() < 1e-11);
+            let x = find_root_brent(f, lo, hi, 1e-12).unwrap();
+            assert!((x - root).abs() < 1e-11);
+            // brackets given the other way round
+            let x = find_root_bisection(f, hi, lo, Stop::Tolerance(1e-12)).unwrap();
+            assert!((x - root).abs() < 1e-11);
+            let x = find_root_brent(f, hi, lo, 1e-12).unwrap();
+            assert!((x - root).abs() < 1e-11);
+        }
+
+        assert_eq!(find_root_brent(f, 1.0, 4.0, 1e-12), Some(1.0));
+        assert_eq!(find_root_brent(f, 2.0, 4.0, 1e-12), None);
+        assert_eq!(find_root_bisection(f, 2.0, 4.0, Stop::Iterations(60)), None);
+
+        // brent needs far fewer evaluations on smooth functions
+        let mut evaluations = 0;
+        find_root_brent(
+            |x: f64| {
+                evaluations += 1;
+                x.exp() - 10.0
+            },
+            0.0,
+            10.0,
+            1e-12,
+        );
+        assert!(evaluations < 20, "{}", evaluations);
+    }
+
+    #[test]
+    fn test_unimodal() {
+        let f = |x: f64| (x - 1.5).abs() + 2.0;
+        assert!((ternary_search(-100.0, 100.0, f, 1e-9) - 1.5).abs() < 1e-8);
+        assert!((golden_section_search(-100.0, 100.0, f, 1e-9) - 1.5).abs() < 1e-8);
+
+        // minimum at an end of the bracket
+        let g = |x: f64| x;
+        assert!(ternary_search(0.0, 1.0, g, 1e-9) < 1e-8);
+        assert!(golden_section_search(0.0, 1.0, g, 1e-9) < 1e-8);
+        assert!(ternary_search(0.0, 1.0, g, 0.0) < 1e-12);
+    }
+}
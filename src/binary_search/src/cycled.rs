@@ -0,0 +1,303 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Searches in sorted slices that have been rotated, such as snapshots of a
+//! ring buffer, where values may repeat.
+//!
+//! Positions "in sorted order" refer to the slice as it was before the
+//! rotation; returned indices are always indices into the rotated slice.
+
+use super::{lower_bound, upper_bound};
+
+/// Find the index where the sorted order starts in a sorted cycled slice
+///
+/// # Arguments
+/// * `a` - A sorted cycled slice that hold values
+///
+/// # Return
+/// Index of the first element in sorted order, `0` if `a` is not rotated or
+/// empty. O(log n) for unique values, O(n) in the worst case with duplicates
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::find_rotation_point;
+///
+/// assert_eq!(find_rotation_point(&[5, 6, 7, 1, 2, 3]), 3);
+/// assert_eq!(find_rotation_point(&[2, 2, 1, 2, 2]), 2);
+/// assert_eq!(find_rotation_point(&[1, 2, 3]), 0);
+/// ```
+pub fn find_rotation_point<T: PartialOrd>(a: &[T]) -> usize {
+    if a.is_empty() {
+        return 0;
+    }
+
+    let mut low = 0;
+    let mut high = a.len() - 1;
+
+    // invariant: the rotation point is in [low, high]
+    while low < high {
+        let mid = low + (high - low) / 2;
+
+        if a[mid] > a[high] {
+            low = mid + 1;
+        } else if a[mid] < a[high] {
+            high = mid;
+        } else if a[high - 1] > a[high] {
+            // a[mid] == a[high] says nothing about which side is ordered,
+            // only a descent marks the rotation point
+            return high;
+        } else {
+            high -= 1;
+        }
+    }
+
+    low
+}
+
+/// Binary search the element which equals to value in a sorted cycled slice
+/// that may hold duplicates
+///
+/// # Arguments
+/// * `a` - A sorted cycled slice that hold values
+/// * `v` - The value to be searched
+///
+/// # Return
+/// Index of the value. O(log n) for unique values, O(n) in the worst case
+/// with duplicates
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::binary_search_cycled_dup;
+///
+/// let a = vec![3, 3, 3, 1, 2, 3, 3];
+/// assert_eq!(binary_search_cycled_dup(&a, 1), Some(3));
+/// assert_eq!(binary_search_cycled_dup(&a, 4), None);
+/// ```
+pub fn binary_search_cycled_dup<T: PartialOrd>(a: &[T], v: T) -> Option<usize> {
+    binary_search_cycled_first(a, v)
+}
+
+/// Binary search the first element in sorted order which equals to value in
+/// a sorted cycled slice
+///
+/// # Arguments
+/// * `a` - A sorted cycled slice that hold values
+/// * `v` - The value to be searched
+///
+/// # Return
+/// Index of the value
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::binary_search_cycled_first;
+///
+/// // [1, 2, 5, 5, 5] rotated by 3
+/// let a = vec![5, 5, 1, 2, 5];
+/// assert_eq!(binary_search_cycled_first(&a, 5), Some(4));
+/// ```
+pub fn binary_search_cycled_first<T: PartialOrd>(a: &[T], v: T) -> Option<usize> {
+    let r = find_rotation_point(a);
+    let i = to_index(a, r, sorted_lower_bound(a, r, &v));
+
+    if i < a.len() && a[i] == v {
+        Some(i)
+    } else {
+        None
+    }
+}
+
+/// Binary search the last element in sorted order which equals to value in
+/// a sorted cycled slice
+///
+/// # Arguments
+/// * `a` - A sorted cycled slice that hold values
+/// * `v` - The value to be searched
+///
+/// # Return
+/// Index of the value
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::binary_search_cycled_last;
+///
+/// // [1, 2, 5, 5, 5] rotated by 3
+/// let a = vec![5, 5, 1, 2, 5];
+/// assert_eq!(binary_search_cycled_last(&a, 5), Some(1));
+/// ```
+pub fn binary_search_cycled_last<T: PartialOrd>(a: &[T], v: T) -> Option<usize> {
+    let r = find_rotation_point(a);
+    let j = sorted_upper_bound(a, r, &v);
+
+    if j == 0 {
+        return None;
+    }
+
+    let i = to_index(a, r, j - 1);
+    if a[i] == v {
+        Some(i)
+    } else {
+        None
+    }
+}
+
+/// Rotates a sorted cycled slice back into sorted order
+///
+/// # Return
+/// The rotation point of `a` before the call
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::unrotate;
+///
+/// let mut a = vec![4, 4, 5, 1, 2, 4];
+/// assert_eq!(unrotate(&mut a), 3);
+/// assert_eq!(a, vec![1, 2, 4, 4, 4, 5]);
+/// ```
+pub fn unrotate<T: PartialOrd>(a: &mut [T]) -> usize {
+    let r = find_rotation_point(a);
+    a.rotate_left(r);
+    r
+}
+
+/// Iterates over a sorted cycled slice in sorted order without moving it
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::iter_unrotated;
+///
+/// let a = vec![4, 5, 1, 2, 3];
+/// let sorted: Vec<i32> = iter_unrotated(&a).cloned().collect();
+/// assert_eq!(sorted, vec![1, 2, 3, 4, 5]);
+/// ```
+pub fn iter_unrotated<T: PartialOrd>(a: &[T]) -> impl Iterator<Item = &T> {
+    let (front, back) = a.split_at(find_rotation_point(a));
+    back.iter().chain(front)
+}
+
+/// `lower_bound` on `a[r..] ++ a[..r]`, as a position in that order.
+fn sorted_lower_bound<T: PartialOrd>(a: &[T], r: usize, v: &T) -> usize {
+    let (front, back) = a.split_at(r);
+    let i = lower_bound(back, v);
+    if i < back.len() {
+        i
+    } else {
+        back.len() + lower_bound(front, v)
+    }
+}
+
+/// `upper_bound` on `a[r..] ++ a[..r]`, as a position in that order.
+fn sorted_upper_bound<T: PartialOrd>(a: &[T], r: usize, v: &T) -> usize {
+    let (front, back) = a.split_at(r);
+    let j = upper_bound(back, v);
+    if j < back.len() {
+        j
+    } else {
+        back.len() + upper_bound(front, v)
+    }
+}
+
+/// Maps a position in sorted order to an index into `a`, `a.len()` stays.
+fn to_index<T>(a: &[T], r: usize, i: usize) -> usize {
+    let n = a.len();
+    if i >= n {
+        n
+    } else if i < n - r {
+        r + i
+    } else {
+        i - (n - r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::sorted_inputs;
+
+    #[test]
+    fn test_against_linear_scan() {
+        for sorted in sorted_inputs() {
+            let n = sorted.len();
+            let max = sorted.last().cloned().unwrap_or(0) + 2;
+
+            for k in 0..n.max(1) {
+                let mut a = sorted.clone();
+                a.rotate_right(k);
+                let r = if n == 0 { 0 } else { k % n };
+
+                // a rotated slice with duplicates can have several valid
+                // rotation points; every one of them unrotates it
+                let found = find_rotation_point(&a);
+                let mut b = a.clone();
+                b.rotate_left(found);
+                assert_eq!(b, sorted);
+                let rotated: Vec<u64> = iter_unrotated(&a).cloned().collect();
+                assert_eq!(rotated, sorted);
+                let mut b = a.clone();
+                assert_eq!(unrotate(&mut b), found);
+                assert_eq!(b, sorted);
+
+                for v in 0..max {
+                    let dup = binary_search_cycled_dup(&a, v);
+                    assert_eq!(dup.is_some(), a.contains(&v));
+                    if let Some(i) = dup {
+                        assert_eq!(a[i], v);
+                    }
+
+                    // first and last in sorted order, mapped back through
+                    // the actual rotation
+                    let index = |i: usize| (i + r) % n;
+                    let first = sorted.iter().position(|&x| x == v).map(&index);
+                    let last = sorted.iter().rposition(|&x| x == v).map(&index);
+                    assert_eq!(
+                        binary_search_cycled_first(&a, v).map(|i| a[i]),
+                        first.map(|i| a[i])
+                    );
+                    assert_eq!(
+                        binary_search_cycled_last(&a, v).map(|i| a[i]),
+                        last.map(|i| a[i])
+                    );
+                    if found == r {
+                        assert_eq!(binary_search_cycled_first(&a, v), first);
+                        assert_eq!(binary_search_cycled_last(&a, v), last);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_timestamps() {
+        // ring buffer snapshot, written at index 6 next
+        let a = vec![40, 40, 50, 50, 50, 60, 10, 20, 20, 30, 40];
+        assert_eq!(find_rotation_point(&a), 6);
+        assert_eq!(binary_search_cycled_first(&a, 40), Some(10));
+        assert_eq!(binary_search_cycled_last(&a, 40), Some(1));
+        assert_eq!(binary_search_cycled_first(&a, 20), Some(7));
+        assert_eq!(binary_search_cycled_last(&a, 20), Some(8));
+        assert_eq!(binary_search_cycled_first(&a, 35), None);
+        assert_eq!(binary_search_cycled_last(&a, 70), None);
+    }
+}
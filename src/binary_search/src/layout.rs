@@ -0,0 +1,287 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sorted arrays laid out for cache-friendly searching.
+//!
+//! Bisection on a large array touches a new cache line at almost every step
+//! and the next address depends on the last comparison. These layouts put the
+//! elements a search visits close together, compare without branches and
+//! prefetch the next levels ahead of time.
+//!
+//! reference:
+//!     https://algorithmica.org/en/eytzinger
+//!     https://algorithmica.org/en/b-tree
+
+/// A sorted array stored in breadth-first order of the implicit binary
+/// search tree over it: the children of node `k` are `2k` and `2k + 1`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::EytzingerArray;
+///
+/// let a = EytzingerArray::new(&[1, 3, 3, 5, 8]);
+/// assert_eq!(a.lower_bound(&4), Some(&5));
+/// assert_eq!(a.lower_bound(&9), None);
+/// assert!(a.contains(&3));
+/// ```
+#[derive(Debug, Clone)]
+pub struct EytzingerArray<T> {
+    // 1-based, `tree[0]` is a copy of the first element that is never read
+    tree: Vec<T>,
+}
+
+impl<T: PartialOrd + Copy> EytzingerArray<T> {
+    /// Builds the layout from the sorted slice `a`.
+    pub fn new(a: &[T]) -> EytzingerArray<T> {
+        debug_assert!(a.windows(2).all(|w| w[0] <= w[1]), "not sorted");
+
+        let mut tree = Vec::with_capacity(a.len() + 1);
+        if let Some(&first) = a.first() {
+            tree.resize(a.len() + 1, first);
+            let mut next = 0;
+            eytzinger_fill(a, &mut tree, &mut next, 1);
+        }
+
+        EytzingerArray { tree }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len().saturating_sub(1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the first element not less than `v`.
+    pub fn lower_bound(&self, v: &T) -> Option<&T> {
+        let n = self.len();
+        // the 16 descendants four levels down share one or two cache lines
+        let ahead = 16;
+
+        let mut k = 1;
+        while k <= n {
+            prefetch(self.tree.as_ptr().wrapping_add(k * ahead));
+            k = 2 * k + (self.tree[k] < *v) as usize;
+        }
+
+        // the answer is where the search last went left: strip the trailing
+        // right turns and that left turn
+        k >>= k.trailing_ones() + 1;
+        if k == 0 {
+            None
+        } else {
+            Some(&self.tree[k])
+        }
+    }
+
+    /// Returns `true` if an element equals `v`.
+    pub fn contains(&self, v: &T) -> bool {
+        self.lower_bound(v) == Some(v)
+    }
+}
+
+/// Writes `a` into the subtree rooted at `k` in order.
+fn eytzinger_fill<T: Copy>(a: &[T], tree: &mut [T], next: &mut usize, k: usize) {
+    if k < tree.len() {
+        eytzinger_fill(a, tree, next, 2 * k);
+        tree[k] = a[*next];
+        *next += 1;
+        eytzinger_fill(a, tree, next, 2 * k + 1);
+    }
+}
+
+/// A sorted array stored as a static B+ tree with `B` keys per node.
+///
+/// The leaves are the sorted elements themselves, `B` per node. Above them
+/// each layer has nodes of `B` separator keys and `B + 1` children, child `i`
+/// of node `k` being node `k * (B + 1) + i` of the layer below, and separator
+/// `i` the smallest element under child `i + 1`. The layers are stored one
+/// after the other, so a search reads one node per layer, ideally one cache
+/// line: `B * size_of::<T>()` should be 64.
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::SearchTree;
+///
+/// let a: Vec<u32> = (0..1000).map(|x| x * 2).collect();
+/// let t: SearchTree<u32, 16> = SearchTree::new(&a);
+/// assert_eq!(t.lower_bound(&501), Some(&502));
+/// assert!(!t.contains(&501));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SearchTree<T, const B: usize> {
+    // the leaves followed by the internal layers up to the root, every layer
+    // padded to whole nodes with copies of the greatest element
+    tree: Vec<T>,
+    // where each layer starts in `tree`, the leaves first
+    layers: Vec<usize>,
+    len: usize,
+}
+
+impl<T: PartialOrd + Copy, const B: usize> SearchTree<T, B> {
+    /// Builds the layout from the sorted slice `a`.
+    pub fn new(a: &[T]) -> SearchTree<T, B> {
+        const { assert!(B > 0, "nodes need at least one key") };
+        debug_assert!(a.windows(2).all(|w| w[0] <= w[1]), "not sorted");
+
+        let mut tree = Vec::new();
+        let mut layers = Vec::new();
+        let last = match a.last() {
+            Some(&last) => last,
+            None => {
+                return SearchTree {
+                    tree,
+                    layers,
+                    len: 0,
+                }
+            }
+        };
+
+        let mut nodes = a.len().div_ceil(B);
+        layers.push(0);
+        tree.extend_from_slice(a);
+        tree.resize(nodes * B, last);
+
+        // number of elements under a child of a node in the current layer
+        let mut span = B;
+        while nodes > 1 {
+            nodes = nodes.div_ceil(B + 1);
+            layers.push(tree.len());
+            for k in 0..nodes {
+                for i in 0..B {
+                    let first = (k * (B + 1) + i + 1).saturating_mul(span);
+                    tree.push(a.get(first).cloned().unwrap_or(last));
+                }
+            }
+            span = span.saturating_mul(B + 1);
+        }
+
+        SearchTree {
+            tree,
+            layers,
+            len: a.len(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of `keys` less than `v`. Counting instead of searching keeps
+    /// the loop free of branches.
+    #[inline]
+    fn rank(keys: &[T], v: &T) -> usize {
+        keys.iter().filter(|&x| x < v).count()
+    }
+
+    /// Returns the first element not less than `v`.
+    pub fn lower_bound(&self, v: &T) -> Option<&T> {
+        // past the greatest element the padding would lead out of the tree
+        match self.tree.get(self.len.wrapping_sub(1)) {
+            Some(last) if v <= last => {}
+            _ => return None,
+        }
+
+        let mut k = 0;
+        for h in (1..self.layers.len()).rev() {
+            let node = self.layers[h] + k * B;
+            // the children of this node are next to each other
+            prefetch(
+                self.tree
+                    .as_ptr()
+                    .wrapping_add(self.layers[h - 1] + k * (B + 1) * B),
+            );
+            k = k * (B + 1) + Self::rank(&self.tree[node..node + B], v);
+        }
+
+        // separators are the first elements of the next leaf, so a search
+        // running off the end of a leaf continues into the next one
+        let i = k * B + Self::rank(&self.tree[k * B..(k + 1) * B], v);
+        self.tree[..self.len].get(i)
+    }
+
+    /// Returns `true` if an element equals `v`.
+    pub fn contains(&self, v: &T) -> bool {
+        self.lower_bound(v) == Some(v)
+    }
+}
+
+/// Hints the CPU to load the cache line at `p`. Never faults, so `p` may
+/// point past the end of an allocation.
+#[inline(always)]
+fn prefetch<T>(p: *const T) {
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch(p as *const i8, _MM_HINT_T0);
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = p;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::sorted_inputs;
+
+    #[test]
+    fn test_eytzinger_array() {
+        let a = EytzingerArray::<u32>::new(&[]);
+        assert!(a.is_empty());
+        assert_eq!(a.lower_bound(&0), None);
+
+        for a in sorted_inputs() {
+            let e = EytzingerArray::new(&a);
+            assert_eq!(e.len(), a.len());
+
+            let max = a.last().cloned().unwrap_or(0) + 2;
+            for v in 0..max {
+                assert_eq!(e.lower_bound(&v), a.iter().find(|&&x| x >= v));
+                assert_eq!(e.contains(&v), a.contains(&v));
+            }
+        }
+    }
+
+    fn check_search_tree<const B: usize>() {
+        for a in sorted_inputs() {
+            let t: SearchTree<u64, B> = SearchTree::new(&a);
+            assert_eq!(t.len(), a.len());
+
+            let max = a.last().cloned().unwrap_or(0) + 2;
+            for v in 0..max {
+                assert_eq!(t.lower_bound(&v), a.iter().find(|&&x| x >= v));
+                assert_eq!(t.contains(&v), a.contains(&v));
+            }
+        }
+    }
+
+    #[test]
+    fn test_search_tree() {
+        check_search_tree::<1>();
+        check_search_tree::<2>();
+        check_search_tree::<3>();
+        check_search_tree::<8>();
+        check_search_tree::<16>();
+    }
+}
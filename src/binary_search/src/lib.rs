@@ -12,6 +12,252 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::cmp::Ordering;
+use std::ops::Range;
+
+/// Returns the index of the first element for which `pred` is false
+///
+/// # Arguments
+/// * `a` - A slice partitioned by `pred`: every element for which it holds
+///   comes before every element for which it does not
+/// * `pred` - The predicate
+///
+/// # Return
+/// Index of the partition point, `a.len()` if `pred` holds for all elements
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::partition_point;
+///
+/// let a = vec![1, 2, 3, 3, 5, 6, 7];
+/// assert_eq!(partition_point(&a, |&x| x < 5), 4);
+/// assert_eq!(partition_point(&a, |&x| x < 10), 7);
+/// ```
+pub fn partition_point<T, P>(a: &[T], mut pred: P) -> usize
+where
+    P: FnMut(&T) -> bool,
+{
+    let mut low = 0;
+    let mut high = a.len();
+
+    // invariant: pred holds before `low` and fails from `high` on
+    while low < high {
+        let mid = low + (high - low) / 2;
+
+        if pred(&a[mid]) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    low
+}
+
+/// Returns the index of the first element which is not less than value
+///
+/// # Arguments
+/// * `a` - A sorted slice that hold values
+/// * `v` - The value to be searched
+///
+/// # Return
+/// Index where `v` could be inserted keeping `a` sorted, before any equal
+/// elements
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::lower_bound;
+///
+/// let a = vec![1, 2, 2, 3, 5];
+/// assert_eq!(lower_bound(&a, &2), 1);
+/// assert_eq!(lower_bound(&a, &4), 4);
+/// assert_eq!(lower_bound(&a, &6), 5);
+/// ```
+pub fn lower_bound<T: PartialOrd>(a: &[T], v: &T) -> usize {
+    partition_point(a, |x| x < v)
+}
+
+/// Returns the index of the first element which is greater than value
+///
+/// # Arguments
+/// * `a` - A sorted slice that hold values
+/// * `v` - The value to be searched
+///
+/// # Return
+/// Index where `v` could be inserted keeping `a` sorted, after any equal
+/// elements
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::upper_bound;
+///
+/// let a = vec![1, 2, 2, 3, 5];
+/// assert_eq!(upper_bound(&a, &2), 3);
+/// assert_eq!(upper_bound(&a, &0), 0);
+/// ```
+pub fn upper_bound<T: PartialOrd>(a: &[T], v: &T) -> usize {
+    partition_point(a, |x| x <= v)
+}
+
+/// Returns the range of elements which equal to value
+///
+/// # Arguments
+/// * `a` - A sorted slice that hold values
+/// * `v` - The value to be searched
+///
+/// # Return
+/// `lower_bound(a, v)..upper_bound(a, v)`, empty if `v` is not in `a`
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::equal_range;
+///
+/// let a = vec![1, 2, 2, 2, 3, 5];
+/// assert_eq!(equal_range(&a, &2), 1..4);
+/// assert_eq!(equal_range(&a, &4), 5..5);
+/// ```
+pub fn equal_range<T: PartialOrd>(a: &[T], v: &T) -> Range<usize> {
+    equal_range_by(a, |x| {
+        if x < v {
+            Ordering::Less
+        } else if x > v {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    })
+}
+
+/// `lower_bound` with a comparator that orders each element against the
+/// value searched, as in `slice::binary_search_by`
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::lower_bound_by;
+///
+/// let a = vec![5, 4, 4, 2, 1];
+/// assert_eq!(lower_bound_by(&a, |x| 4.cmp(x)), 1);
+/// ```
+pub fn lower_bound_by<T, F>(a: &[T], mut f: F) -> usize
+where
+    F: FnMut(&T) -> Ordering,
+{
+    partition_point(a, |x| f(x) == Ordering::Less)
+}
+
+/// `upper_bound` with a comparator that orders each element against the
+/// value searched, as in `slice::binary_search_by`
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::upper_bound_by;
+///
+/// let a = vec![5, 4, 4, 2, 1];
+/// assert_eq!(upper_bound_by(&a, |x| 4.cmp(x)), 3);
+/// ```
+pub fn upper_bound_by<T, F>(a: &[T], mut f: F) -> usize
+where
+    F: FnMut(&T) -> Ordering,
+{
+    partition_point(a, |x| f(x) != Ordering::Greater)
+}
+
+/// `equal_range` with a comparator that orders each element against the
+/// value searched, as in `slice::binary_search_by`
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::equal_range_by;
+///
+/// let a = vec![5, 4, 4, 2, 1];
+/// assert_eq!(equal_range_by(&a, |x| 4.cmp(x)), 1..3);
+/// ```
+pub fn equal_range_by<T, F>(a: &[T], mut f: F) -> Range<usize>
+where
+    F: FnMut(&T) -> Ordering,
+{
+    let low = lower_bound_by(a, &mut f);
+    // the equal elements can only follow the lower bound
+    let high = low + upper_bound_by(&a[low..], &mut f);
+    low..high
+}
+
+/// `lower_bound` on the keys extracted by `f`, as in
+/// `slice::binary_search_by_key`
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::lower_bound_by_key;
+///
+/// let a = vec![(1, 'a'), (3, 'b'), (3, 'c'), (7, 'd')];
+/// assert_eq!(lower_bound_by_key(&a, &3, |&(k, _)| k), 1);
+/// ```
+pub fn lower_bound_by_key<T, B, F>(a: &[T], b: &B, mut f: F) -> usize
+where
+    B: PartialOrd,
+    F: FnMut(&T) -> B,
+{
+    partition_point(a, |x| f(x) < *b)
+}
+
+/// `upper_bound` on the keys extracted by `f`, as in
+/// `slice::binary_search_by_key`
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::upper_bound_by_key;
+///
+/// let a = vec![(1, 'a'), (3, 'b'), (3, 'c'), (7, 'd')];
+/// assert_eq!(upper_bound_by_key(&a, &3, |&(k, _)| k), 3);
+/// ```
+pub fn upper_bound_by_key<T, B, F>(a: &[T], b: &B, mut f: F) -> usize
+where
+    B: PartialOrd,
+    F: FnMut(&T) -> B,
+{
+    partition_point(a, |x| f(x) <= *b)
+}
+
+/// `equal_range` on the keys extracted by `f`, as in
+/// `slice::binary_search_by_key`
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::equal_range_by_key;
+///
+/// let a = vec![(1, 'a'), (3, 'b'), (3, 'c'), (7, 'd')];
+/// assert_eq!(equal_range_by_key(&a, &3, |&(k, _)| k), 1..3);
+/// ```
+pub fn equal_range_by_key<T, B, F>(a: &[T], b: &B, mut f: F) -> Range<usize>
+where
+    B: PartialOrd,
+    F: FnMut(&T) -> B,
+{
+    let low = lower_bound_by_key(a, b, &mut f);
+    let high = low + upper_bound_by_key(&a[low..], b, &mut f);
+    low..high
+}
+
 /// Basic binary search
 ///
 /// # Arguments
@@ -32,23 +278,12 @@
 /// assert_eq!(binary_search(&a, 10), None);
 /// ```
 pub fn binary_search<T: PartialOrd>(a: &[T], v: T) -> Option<usize> {
-    let mut low = 0i32;
-    let mut high = a.len() as i32 - 1;
-
-    while low <= high {
-        // prevent overflow
-        let mid = low + ((high - low) >> 1);
-
-        if a[mid as usize] == v {
-            return Some(mid as usize);
-        } else if a[mid as usize] < v {
-            low = mid + 1;
-        } else {
-            high = mid - 1;
-        }
+    let i = lower_bound(a, &v);
+    if i < a.len() && a[i] == v {
+        Some(i)
+    } else {
+        None
     }
-
-    None
 }
 
 /// Binary search the first element which equals to value
@@ -71,27 +306,7 @@ pub fn binary_search<T: PartialOrd>(a: &[T], v: T) -> Option<usize> {
 /// assert_eq!(binary_search_first(&a, 10), None);
 /// ```
 pub fn binary_search_first<T: PartialOrd>(a: &[T], v: T) -> Option<usize> {
-    let mut low = 0i32;
-    let mut high = a.len() as i32 - 1;
-
-    while low <= high {
-        // prevent overflow
-        let mid = low + ((high - low) >> 1);
-
-        if a[mid as usize] == v {
-            if mid == 0 || a[mid as usize - 1] != v {
-                return Some(mid as usize);
-            } else {
-                high = mid - 1;
-            }
-        } else if a[mid as usize] < v {
-            low = mid + 1;
-        } else {
-            high = mid - 1;
-        }
-    }
-
-    None
+    binary_search(a, v)
 }
 
 /// Binary search the last element which equals to value
@@ -114,27 +329,12 @@ pub fn binary_search_first<T: PartialOrd>(a: &[T], v: T) -> Option<usize> {
 /// assert_eq!(binary_search_last(&a, 10), None);
 /// ```
 pub fn binary_search_last<T: PartialOrd>(a: &[T], v: T) -> Option<usize> {
-    let mut low = 0i32;
-    let mut high = a.len() as i32 - 1;
-
-    while low <= high {
-        // prevent overflow
-        let mid = low + ((high - low) >> 1);
-
-        if a[mid as usize] == v {
-            if mid == (a.len() as i32 - 1) || a[mid as usize + 1] != v {
-                return Some(mid as usize);
-            } else {
-                low = mid + 1;
-            }
-        } else if a[mid as usize] < v {
-            low = mid + 1;
-        } else {
-            high = mid - 1;
-        }
+    let j = upper_bound(a, &v);
+    if j > 0 && a[j - 1] == v {
+        Some(j - 1)
+    } else {
+        None
     }
-
-    None
 }
 
 /// Binary search the first element which greater than or equals to value
@@ -159,25 +359,12 @@ pub fn binary_search_last<T: PartialOrd>(a: &[T], v: T) -> Option<usize> {
 /// assert_eq!(binary_search_first_gte(&a, 10), None);
 /// ```
 pub fn binary_search_first_gte<T: PartialOrd>(a: &[T], v: T) -> Option<usize> {
-    let mut low = 0i32;
-    let mut high = a.len() as i32 - 1;
-
-    while low <= high {
-        // prevent overflow
-        let mid = low + ((high - low) >> 1);
-
-        if a[mid as usize] >= v {
-            if mid == 0 || a[mid as usize - 1] < v {
-                return Some(mid as usize);
-            } else {
-                high = mid - 1;
-            }
-        } else {
-            low = mid + 1;
-        }
+    let i = lower_bound(a, &v);
+    if i < a.len() {
+        Some(i)
+    } else {
+        None
     }
-
-    None
 }
 
 /// Binary search the last element which less than or equals to value
@@ -202,25 +389,7 @@ pub fn binary_search_first_gte<T: PartialOrd>(a: &[T], v: T) -> Option<usize> {
 /// assert_eq!(binary_search_last_lte(&a, 10), Some(8));
 /// ```
 pub fn binary_search_last_lte<T: PartialOrd>(a: &[T], v: T) -> Option<usize> {
-    let mut low = 0i32;
-    let mut high = a.len() as i32 - 1;
-
-    while low <= high {
-        // prevent overflow
-        let mid = low + ((high - low) >> 1);
-
-        if a[mid as usize] <= v {
-            if mid == (a.len() as i32 - 1) || a[mid as usize + 1] > v {
-                return Some(mid as usize);
-            } else {
-                low = mid + 1;
-            }
-        } else {
-            high = mid - 1;
-        }
-    }
-
-    None
+    upper_bound(a, &v).checked_sub(1)
 }
 
 /// Binary search the element which equals to value in a sorted cycled slice
@@ -243,29 +412,29 @@ pub fn binary_search_last_lte<T: PartialOrd>(a: &[T], v: T) -> Option<usize> {
 /// assert_eq!(binary_search_cycled(&a, 10), None);
 /// ```
 pub fn binary_search_cycled<T: PartialOrd>(a: &[T], v: T) -> Option<usize> {
-    let mut low = 0i32;
-    let mut high = a.len() as i32 - 1;
+    let mut low = 0;
+    let mut high = a.len();
 
-    while low <= high {
-        // prevent overflow
-        let mid = low + ((high - low) >> 1);
-        let mid_val = &a[mid as usize];
+    // invariant: if `v` is in `a`, it is in `a[low..high]`
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let mid_val = &a[mid];
 
         if *mid_val == v {
-            return Some(mid as usize);
-        } else if a[low as usize] <= *mid_val {
+            return Some(mid);
+        } else if a[low] <= *mid_val {
             // left side is ordered, right side is ordered or cycled
-            if *mid_val > v && a[low as usize] <= v {
-                high = mid - 1;
+            if *mid_val > v && a[low] <= v {
+                high = mid;
             } else {
                 low = mid + 1;
             }
         } else {
             // left side is ordered or cycled, right side is ordered
-            if *mid_val < v && a[high as usize] >= v {
+            if *mid_val < v && a[high - 1] >= v {
                 low = mid + 1;
             } else {
-                high = mid - 1;
+                high = mid;
             }
         }
     }
@@ -273,9 +442,125 @@ pub fn binary_search_cycled<T: PartialOrd>(a: &[T], v: T) -> Option<usize> {
     None
 }
 
+mod batch;
+mod bisect;
+mod cycled;
+mod layout;
+mod matrix;
+mod search;
+mod sorted_vec;
+
+pub use batch::{batch_search, FractionalCascading};
+pub use bisect::{
+    bisect_f64, bisect_int, find_root_bisection, find_root_brent, golden_section_search,
+    ternary_search, BisectInt, Stop,
+};
+pub use cycled::{
+    binary_search_cycled_dup, binary_search_cycled_first, binary_search_cycled_last,
+    find_rotation_point, iter_unrotated, unrotate,
+};
+pub use layout::{EytzingerArray, SearchTree};
+pub use matrix::{
+    find_peak, find_peak_2d, kth_of_two_sorted, kth_smallest_in_matrix, median_of_two_sorted,
+    search_sorted_matrix, search_sorted_matrix_dc,
+};
+pub use search::{
+    exponential_search, fibonacci_search, galloping_search, interpolation_search, Interpolate,
+};
+pub use sorted_vec::{SortedVecMap, SortedVecSet};
+
+#[cfg(test)]
+mod testing;
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use testing::sorted_inputs;
+
+    #[test]
+    fn test_against_linear_scan() {
+        for a in sorted_inputs() {
+            let max = a.last().cloned().unwrap_or(0) + 2;
+            for v in 0..max {
+                let lower = a.iter().position(|&x| x >= v).unwrap_or(a.len());
+                let upper = a.iter().position(|&x| x > v).unwrap_or(a.len());
+                let first = a.iter().position(|&x| x == v);
+                let last = a.iter().rposition(|&x| x == v);
+
+                assert_eq!(partition_point(&a, |&x| x < v), lower);
+                assert_eq!(lower_bound(&a, &v), lower);
+                assert_eq!(upper_bound(&a, &v), upper);
+                assert_eq!(equal_range(&a, &v), lower..upper);
+                assert_eq!(lower_bound_by(&a, |x| x.cmp(&v)), lower);
+                assert_eq!(upper_bound_by(&a, |x| x.cmp(&v)), upper);
+                assert_eq!(equal_range_by(&a, |x| x.cmp(&v)), lower..upper);
+                assert_eq!(lower_bound_by_key(&a, &(v * 3), |&x| x * 3), lower);
+                assert_eq!(upper_bound_by_key(&a, &(v * 3), |&x| x * 3), upper);
+                assert_eq!(equal_range_by_key(&a, &(v * 3), |&x| x * 3), lower..upper);
+
+                match binary_search(&a, v) {
+                    Some(i) => assert_eq!(a[i], v),
+                    None => assert_eq!(first, None),
+                }
+                assert_eq!(binary_search_first(&a, v), first);
+                assert_eq!(binary_search_last(&a, v), last);
+                assert_eq!(
+                    binary_search_first_gte(&a, v),
+                    a.iter().position(|&x| x >= v)
+                );
+                assert_eq!(
+                    binary_search_last_lte(&a, v),
+                    a.iter().rposition(|&x| x <= v)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_binary_search_cycled_against_linear_scan() {
+        for n in 0..40 {
+            let sorted: Vec<u64> = (0..n).map(|x| 2 * x + 1).collect();
+            for k in 0..n.max(1) as usize {
+                let mut a = sorted.clone();
+                a.rotate_left(k);
+                for v in 0..2 * n + 2 {
+                    assert_eq!(binary_search_cycled(&a, v), a.iter().position(|&x| x == v));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_partition_point() {
+        let a: Vec<i32> = vec![];
+        assert_eq!(partition_point(&a, |&x| x < 1), 0);
+
+        let a = vec![1, 2, 2, 3, 5];
+        for v in 0..7 {
+            let expected = a.iter().filter(|&&x| x < v).count();
+            assert_eq!(partition_point(&a, |&x| x < v), expected);
+            assert_eq!(lower_bound(&a, &v), expected);
+
+            let expected = a.iter().filter(|&&x| x <= v).count();
+            assert_eq!(upper_bound(&a, &v), expected);
+            assert_eq!(equal_range(&a, &v), lower_bound(&a, &v)..expected);
+        }
+    }
+
+    #[test]
+    fn test_by_and_by_key() {
+        let a = vec![9, 7, 7, 7, 4, 1];
+        assert_eq!(lower_bound_by(&a, |x| 7.cmp(x)), 1);
+        assert_eq!(upper_bound_by(&a, |x| 7.cmp(x)), 4);
+        assert_eq!(equal_range_by(&a, |x| 7.cmp(x)), 1..4);
+        assert_eq!(equal_range_by(&a, |x| 5.cmp(x)), 4..4);
+
+        let a = vec!["a", "bb", "cc", "ddd"];
+        assert_eq!(lower_bound_by_key(&a, &2, |s| s.len()), 1);
+        assert_eq!(upper_bound_by_key(&a, &2, |s| s.len()), 3);
+        assert_eq!(equal_range_by_key(&a, &2, |s| s.len()), 1..3);
+        assert_eq!(equal_range_by_key(&a, &4, |s| s.len()), 4..4);
+    }
 
     #[test]
     fn test_binary_search() {
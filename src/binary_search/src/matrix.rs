@@ -0,0 +1,457 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Searches in structures sorted along more than one axis.
+//!
+//! Matrices are slices of rows, e.g. `&[Vec<T>]` or `&[[T; N]]`, and must be
+//! rectangular.
+
+use super::{bisect_int, lower_bound, BisectInt};
+
+/// Search the element which equals to value in a matrix whose rows and
+/// columns are sorted, walking a staircase from the top-right corner
+///
+/// # Arguments
+/// * `m` - A matrix whose rows and columns are sorted
+/// * `v` - The value to be searched
+///
+/// # Return
+/// Row and column of the value, in O(rows + columns)
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::search_sorted_matrix;
+///
+/// let m = [[1, 4, 7], [2, 5, 8], [3, 6, 9]];
+/// assert_eq!(search_sorted_matrix(&m, &6), Some((2, 1)));
+/// assert_eq!(search_sorted_matrix(&m, &10), None);
+/// ```
+pub fn search_sorted_matrix<T, R>(m: &[R], v: &T) -> Option<(usize, usize)>
+where
+    T: PartialOrd,
+    R: AsRef<[T]>,
+{
+    let cols = columns(m);
+    let mut row = 0;
+    let mut col = cols;
+
+    // everything right of `col` is greater than v, everything above `row`
+    // is less
+    while row < m.len() && col > 0 {
+        let x = &m[row].as_ref()[col - 1];
+        if *x == *v {
+            return Some((row, col - 1));
+        } else if *x > *v {
+            col -= 1;
+        } else {
+            row += 1;
+        }
+    }
+
+    None
+}
+
+/// Search the element which equals to value in a matrix whose rows and
+/// columns are sorted, by divide and conquer
+///
+/// Binary searches the middle row, which rules out the block above and left
+/// of the split point and the block below and right of it, then recurses
+/// into the other two. Fewer comparisons than the staircase when the matrix
+/// is much wider than tall.
+///
+/// # Arguments
+/// * `m` - A matrix whose rows and columns are sorted
+/// * `v` - The value to be searched
+///
+/// # Return
+/// Row and column of the value
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::search_sorted_matrix_dc;
+///
+/// let m = [[1, 4, 7], [2, 5, 8], [3, 6, 9]];
+/// assert_eq!(search_sorted_matrix_dc(&m, &6), Some((2, 1)));
+/// assert_eq!(search_sorted_matrix_dc(&m, &0), None);
+/// ```
+pub fn search_sorted_matrix_dc<T, R>(m: &[R], v: &T) -> Option<(usize, usize)>
+where
+    T: PartialOrd,
+    R: AsRef<[T]>,
+{
+    search_block(m, v, 0, m.len(), 0, columns(m))
+}
+
+/// Searches rows `[r0, r1)` and columns `[c0, c1)`.
+fn search_block<T, R>(
+    m: &[R],
+    v: &T,
+    r0: usize,
+    r1: usize,
+    c0: usize,
+    c1: usize,
+) -> Option<(usize, usize)>
+where
+    T: PartialOrd,
+    R: AsRef<[T]>,
+{
+    if r0 >= r1 || c0 >= c1 {
+        return None;
+    }
+
+    let row = r0 + (r1 - r0) / 2;
+    let cells = &m[row].as_ref()[c0..c1];
+    let c = c0 + lower_bound(cells, v);
+    if c < c1 && cells[c - c0] == *v {
+        return Some((row, c));
+    }
+
+    // m[..row][..c] < v and m[row + 1..][c..] > v
+    search_block(m, v, r0, row, c, c1).or_else(|| search_block(m, v, row + 1, r1, c0, c))
+}
+
+/// Find the k-th smallest element, counting from 0, of a matrix whose rows
+/// and columns are sorted
+///
+/// Bisects on the value, counting the elements not greater than it along a
+/// staircase, so it takes O((rows + columns) log(max - min)).
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::kth_smallest_in_matrix;
+///
+/// let m = vec![vec![1, 5, 9], vec![10, 11, 13], vec![12, 13, 15]];
+/// assert_eq!(kth_smallest_in_matrix(&m, 7), Some(13));
+/// assert_eq!(kth_smallest_in_matrix(&m, 9), None);
+/// ```
+pub fn kth_smallest_in_matrix<T, R>(m: &[R], k: usize) -> Option<T>
+where
+    T: BisectInt,
+    R: AsRef<[T]>,
+{
+    let cols = columns(m);
+    if k >= m.len() * cols {
+        return None;
+    }
+
+    let min = m[0].as_ref()[0];
+    let max = m[m.len() - 1].as_ref()[cols - 1];
+
+    // the smallest x with more than k elements not greater than it is an
+    // element itself; if none is found below max, the answer is max
+    Some(bisect_int(min, max, |x| count_not_greater(m, cols, x) > k))
+}
+
+fn count_not_greater<T, R>(m: &[R], cols: usize, x: T) -> usize
+where
+    T: PartialOrd,
+    R: AsRef<[T]>,
+{
+    let mut count = 0;
+    let mut col = cols;
+    for row in m {
+        while col > 0 && row.as_ref()[col - 1] > x {
+            col -= 1;
+        }
+        count += col;
+    }
+    count
+}
+
+/// Find the k-th smallest element, counting from 0, of the union of two
+/// sorted slices, in O(log(min(m, n)))
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::kth_of_two_sorted;
+///
+/// let a = vec![1, 3, 5, 7];
+/// let b = vec![2, 4];
+/// assert_eq!(kth_of_two_sorted(&a, &b, 4), Some(&5));
+/// assert_eq!(kth_of_two_sorted(&a, &b, 6), None);
+/// ```
+pub fn kth_of_two_sorted<'a, T: PartialOrd>(a: &'a [T], b: &'a [T], k: usize) -> Option<&'a T> {
+    let (a, b) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    let (m, n) = (a.len(), b.len());
+
+    if k >= m + n {
+        return None;
+    }
+
+    // take `i` of the first k + 1 from `a` and `k + 1 - i` from `b`; too
+    // few are taken from `a` while a[i] < b[k - i]
+    let low = (k + 1).saturating_sub(n);
+    let high = m.min(k + 1);
+    let i = bisect_int(low, high, |i| a[i] >= b[k - i]);
+    let j = k + 1 - i;
+
+    match (
+        i.checked_sub(1).map(|i| &a[i]),
+        j.checked_sub(1).map(|j| &b[j]),
+    ) {
+        (Some(x), Some(y)) => Some(if x < y { y } else { x }),
+        (x, y) => x.or(y),
+    }
+}
+
+/// Median of the union of two sorted slices, the mean of the two middle
+/// values for even lengths, in O(log(min(m, n)))
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::median_of_two_sorted;
+///
+/// assert_eq!(median_of_two_sorted(&[1.0, 3.0], &[2.0]), Some(2.0));
+/// assert_eq!(median_of_two_sorted(&[1.0, 2.0], &[3.0, 4.0]), Some(2.5));
+/// assert_eq!(median_of_two_sorted(&[], &[]), None);
+/// ```
+pub fn median_of_two_sorted(a: &[f64], b: &[f64]) -> Option<f64> {
+    let n = a.len() + b.len();
+    if n == 0 {
+        return None;
+    }
+
+    let upper = *kth_of_two_sorted(a, b, n / 2)?;
+    if n % 2 == 1 {
+        Some(upper)
+    } else {
+        let lower = *kth_of_two_sorted(a, b, n / 2 - 1)?;
+        Some(lower + (upper - lower) / 2.0)
+    }
+}
+
+/// Find a peak, an element not less than its neighbours, in O(log n)
+///
+/// # Return
+/// Index of a peak, `None` if `a` is empty
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::find_peak;
+///
+/// let a = vec![1, 3, 20, 4, 1, 0];
+/// assert_eq!(find_peak(&a), Some(2));
+/// ```
+pub fn find_peak<T: PartialOrd>(a: &[T]) -> Option<usize> {
+    if a.is_empty() {
+        return None;
+    }
+
+    // climbing from any point reaches a peak, so a rising step means
+    // there is one on its right
+    Some(bisect_int(0, a.len() - 1, |i| a[i] >= a[i + 1]))
+}
+
+/// Find a peak, an element not less than its up to four neighbours, in a
+/// matrix in O(rows log columns)
+///
+/// # Return
+/// Row and column of a peak, `None` if the matrix is empty
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::find_peak_2d;
+///
+/// let m = [[10, 8, 10, 10], [14, 13, 12, 11], [15, 9, 11, 21], [16, 17, 19, 20]];
+/// let (r, c) = find_peak_2d(&m).unwrap();
+/// assert!(m[r][c] == 21 || m[r][c] == 17);
+/// ```
+pub fn find_peak_2d<T, R>(m: &[R]) -> Option<(usize, usize)>
+where
+    T: PartialOrd,
+    R: AsRef<[T]>,
+{
+    let cols = columns(m);
+    if m.is_empty() || cols == 0 {
+        return None;
+    }
+
+    let column_max = |c: usize| {
+        (1..m.len()).fold(0, |best, r| {
+            if m[r].as_ref()[c] > m[best].as_ref()[c] {
+                r
+            } else {
+                best
+            }
+        })
+    };
+
+    // the maximum of a column is a peak unless its right neighbour is
+    // greater, and then the columns to the right contain one
+    let c = bisect_int(0, cols - 1, |c| {
+        let r = column_max(c);
+        m[r].as_ref()[c] >= m[r].as_ref()[c + 1]
+    });
+    Some((column_max(c), c))
+}
+
+fn columns<T, R: AsRef<[T]>>(m: &[R]) -> usize {
+    let cols = m.first().map_or(0, |row| row.as_ref().len());
+    debug_assert!(
+        m.iter().all(|row| row.as_ref().len() == cols),
+        "not rectangular"
+    );
+    cols
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::XorShift;
+
+    fn sorted_matrix(rng: &mut XorShift, rows: usize, cols: usize) -> Vec<Vec<u64>> {
+        let mut m = vec![vec![0; cols]; rows];
+        for r in 0..rows {
+            for c in 0..cols {
+                let up = if r > 0 { m[r - 1][c] } else { 0 };
+                let left = if c > 0 { m[r][c - 1] } else { 0 };
+                m[r][c] = up.max(left) + rng.below(3);
+            }
+        }
+        m
+    }
+
+    #[test]
+    fn test_search_sorted_matrix() {
+        let mut rng = XorShift(0x3a7);
+
+        for &(rows, cols) in &[
+            (0, 0),
+            (1, 0),
+            (1, 1),
+            (1, 9),
+            (9, 1),
+            (5, 5),
+            (4, 30),
+            (30, 4),
+        ] {
+            let m = sorted_matrix(&mut rng, rows, cols);
+            for v in 0..2 * (rows + cols) as u64 + 2 {
+                let present = m.iter().any(|row| row.contains(&v));
+
+                let found = search_sorted_matrix(&m, &v);
+                assert_eq!(found.is_some(), present);
+                if let Some((r, c)) = found {
+                    assert_eq!(m[r][c], v);
+                }
+
+                let found = search_sorted_matrix_dc(&m, &v);
+                assert_eq!(found.is_some(), present);
+                if let Some((r, c)) = found {
+                    assert_eq!(m[r][c], v);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_kth_smallest_in_matrix() {
+        let mut rng = XorShift(0x4b);
+
+        for &(rows, cols) in &[(1, 1), (1, 7), (7, 1), (6, 6), (3, 20)] {
+            let m = sorted_matrix(&mut rng, rows, cols);
+            let mut all: Vec<u64> = m.iter().flat_map(|row| row.iter().cloned()).collect();
+            all.sort();
+
+            for (k, &x) in all.iter().enumerate() {
+                assert_eq!(kth_smallest_in_matrix(&m, k), Some(x));
+            }
+            assert_eq!(kth_smallest_in_matrix(&m, all.len()), None);
+        }
+
+        let m: Vec<Vec<i32>> = vec![vec![-5, -1], vec![-3, 4]];
+        assert_eq!(kth_smallest_in_matrix(&m, 1), Some(-3));
+        assert_eq!(kth_smallest_in_matrix::<i32, Vec<i32>>(&[], 0), None);
+    }
+
+    #[test]
+    fn test_two_sorted() {
+        let mut rng = XorShift(0x2502);
+
+        for _ in 0..200 {
+            let mut a: Vec<u64> = (0..rng.below(12)).map(|_| rng.below(20)).collect();
+            let mut b: Vec<u64> = (0..rng.below(12)).map(|_| rng.below(20)).collect();
+            a.sort();
+            b.sort();
+            let mut all: Vec<u64> = a.iter().chain(&b).cloned().collect();
+            all.sort();
+
+            for (k, x) in all.iter().enumerate() {
+                assert_eq!(kth_of_two_sorted(&a, &b, k), Some(x));
+            }
+            assert_eq!(kth_of_two_sorted(&a, &b, all.len()), None);
+
+            let a: Vec<f64> = a.iter().map(|&x| x as f64).collect();
+            let b: Vec<f64> = b.iter().map(|&x| x as f64).collect();
+            let n = all.len();
+            let expected = match n {
+                0 => None,
+                _ if n % 2 == 1 => Some(all[n / 2] as f64),
+                _ => Some((all[n / 2 - 1] + all[n / 2]) as f64 / 2.0),
+            };
+            assert_eq!(median_of_two_sorted(&a, &b), expected);
+        }
+    }
+
+    #[test]
+    fn test_find_peak() {
+        assert_eq!(find_peak::<u64>(&[]), None);
+        assert_eq!(find_peak(&[1]), Some(0));
+        assert_eq!(find_peak(&[1, 2, 3]), Some(2));
+        assert_eq!(find_peak(&[3, 2, 1]), Some(0));
+
+        let mut rng = XorShift(0x9ea);
+        for n in 1..50 {
+            let a: Vec<u64> = (0..n).map(|_| rng.below(10)).collect();
+            let i = find_peak(&a).unwrap();
+            assert!(i == 0 || a[i - 1] <= a[i]);
+            assert!(i == n - 1 || a[i + 1] <= a[i]);
+        }
+    }
+
+    #[test]
+    fn test_find_peak_2d() {
+        assert_eq!(find_peak_2d::<u64, Vec<u64>>(&[]), None);
+        assert_eq!(find_peak_2d(&[Vec::<u64>::new()]), None);
+
+        let mut rng = XorShift(0x2d);
+        for &(rows, cols) in &[(1, 1), (1, 8), (8, 1), (5, 5), (3, 17), (17, 3)] {
+            for _ in 0..20 {
+                let m: Vec<Vec<u64>> = (0..rows)
+                    .map(|_| (0..cols).map(|_| rng.below(10)).collect())
+                    .collect();
+                let (r, c) = find_peak_2d(&m).unwrap();
+                let x = m[r][c];
+                assert!(r == 0 || m[r - 1][c] <= x);
+                assert!(r == rows - 1 || m[r + 1][c] <= x);
+                assert!(c == 0 || m[r][c - 1] <= x);
+                assert!(c == cols - 1 || m[r][c + 1] <= x);
+            }
+        }
+    }
+}
@@ -0,0 +1,309 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Alternatives to bisection that exploit where the value is likely to be.
+//!
+//! All of them return `Ok` with the index of the first element equal to the
+//! value, or `Err` with the index where it could be inserted keeping the
+//! slice sorted, like `slice::binary_search` does.
+
+use super::partition_point;
+
+/// Exponential search
+///
+/// Probes indices 0, 1, 3, 7, ... until it passes `v`, then bisects the last
+/// gap, so it takes O(log i) comparisons where `i` is the result. Suits
+/// lookups that are usually near the front of a long slice.
+///
+/// # Arguments
+/// * `a` - A sorted slice that hold values
+/// * `v` - The value to be searched
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::exponential_search;
+///
+/// let a = vec![1, 2, 2, 3, 5, 8, 13];
+/// assert_eq!(exponential_search(&a, &2), Ok(1));
+/// assert_eq!(exponential_search(&a, &4), Err(4));
+/// ```
+pub fn exponential_search<T: PartialOrd>(a: &[T], v: &T) -> Result<usize, usize> {
+    let n = a.len();
+
+    let mut bound = 1;
+    while bound <= n && a[bound - 1] < *v {
+        bound *= 2;
+    }
+
+    // a[bound / 2 - 1] < v, so the result is in [bound / 2, bound - 1]
+    let low = bound / 2;
+    let high = bound.min(n);
+    let i = low + partition_point(&a[low..high], |x| x < v);
+    found(a, v, i)
+}
+
+/// Numeric keys whose relative position between two others can be estimated,
+/// used by `interpolation_search`.
+pub trait Interpolate: PartialOrd {
+    /// Where `self` falls between `low` and `high` as a fraction, `0.0` at
+    /// `low` and `1.0` at `high`. Only called with `low < self <= high`.
+    fn fraction(&self, low: &Self, high: &Self) -> f64;
+}
+
+macro_rules! interpolate_impl {
+    ($($t:ty)*) => {
+        $(
+            impl Interpolate for $t {
+                #[inline]
+                fn fraction(&self, low: &$t, high: &$t) -> f64 {
+                    // subtract in f64, the integer difference could overflow
+                    (*self as f64 - *low as f64) / (*high as f64 - *low as f64)
+                }
+            }
+        )*
+    };
+}
+
+interpolate_impl!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize f32 f64);
+
+/// Interpolation search
+///
+/// Probes where `v` would be if the values were evenly spread between the
+/// ends of the remaining range, O(log log n) comparisons on uniformly
+/// distributed keys. After `log2(n)` probes it finishes with bisection, so
+/// skewed keys cost at most O(log n).
+///
+/// # Arguments
+/// * `a` - A sorted slice that hold values
+/// * `v` - The value to be searched
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::interpolation_search;
+///
+/// let a: Vec<u32> = (0..1000).map(|x| x * 10).collect();
+/// assert_eq!(interpolation_search(&a, &420), Ok(42));
+/// assert_eq!(interpolation_search(&a, &425), Err(43));
+/// ```
+pub fn interpolation_search<T: Interpolate>(a: &[T], v: &T) -> Result<usize, usize> {
+    let mut low = 0;
+    let mut high = a.len();
+    let mut probes = 0;
+
+    // invariant: a[..low] < v <= a[high..]
+    while low < high && probes <= log2(a.len()) {
+        if *v <= a[low] {
+            high = low;
+            break;
+        }
+        if a[high - 1] < *v {
+            low = high;
+            break;
+        }
+
+        // a[low] < v <= a[high - 1]
+        let fraction = v.fraction(&a[low], &a[high - 1]);
+        let offset = if fraction.is_finite() {
+            (fraction * (high - 1 - low) as f64) as usize
+        } else {
+            (high - 1 - low) / 2
+        };
+        let probe = low + offset.min(high - 1 - low);
+
+        if a[probe] < *v {
+            low = probe + 1;
+        } else {
+            high = probe;
+        }
+        probes += 1;
+    }
+
+    let i = low + partition_point(&a[low..high], |x| x < v);
+    found(a, v, i)
+}
+
+/// Fibonacci search
+///
+/// Splits the range at Fibonacci numbers instead of halves, so probe indices
+/// are computed with additions only and successive probes stay close
+/// together.
+///
+/// # Arguments
+/// * `a` - A sorted slice that hold values
+/// * `v` - The value to be searched
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::fibonacci_search;
+///
+/// let a = vec![1, 2, 2, 3, 5, 8, 13];
+/// assert_eq!(fibonacci_search(&a, &8), Ok(5));
+/// assert_eq!(fibonacci_search(&a, &0), Err(0));
+/// ```
+pub fn fibonacci_search<T: PartialOrd>(a: &[T], v: &T) -> Result<usize, usize> {
+    let n = a.len();
+
+    // fib[k] - 1 >= n, the range is padded with virtual elements above v
+    let mut fib = vec![0, 1];
+    while fib[fib.len() - 1] - 1 < n {
+        let next = fib[fib.len() - 1] + fib[fib.len() - 2];
+        fib.push(next);
+    }
+
+    // search a[low..low + fib[k] - 1]
+    let mut low = 0;
+    let mut k = fib.len() - 1;
+    while k >= 3 {
+        let probe = low + fib[k - 1] - 1;
+        if probe < n && a[probe] < *v {
+            // the right part has fib[k] - 1 - fib[k - 1] = fib[k - 2] - 1
+            low = probe + 1;
+            k -= 2;
+        } else {
+            k -= 1;
+        }
+    }
+
+    found(a, v, low)
+}
+
+/// Galloping search
+///
+/// Gallops away from `hint` in steps of 1, 2, 4, ... towards `v`, then
+/// bisects the last step, O(log d) comparisons where `d` is the distance
+/// from the hint to the result. A hint past the end is treated as the last
+/// index.
+///
+/// # Arguments
+/// * `a` - A sorted slice that hold values
+/// * `v` - The value to be searched
+/// * `hint` - Where to start looking
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::galloping_search;
+///
+/// let a: Vec<u32> = (0..100).collect();
+/// assert_eq!(galloping_search(&a, &57, 50), Ok(57));
+/// assert_eq!(galloping_search(&a, &3, 50), Ok(3));
+/// ```
+pub fn galloping_search<T: PartialOrd>(a: &[T], v: &T, hint: usize) -> Result<usize, usize> {
+    let n = a.len();
+
+    if n == 0 {
+        return Err(0);
+    }
+
+    let hint = hint.min(n - 1);
+    let (low, high) = if a[hint] < *v {
+        // gallop right until a[hint + step] >= v
+        let mut last = hint;
+        let mut step = 1;
+        while hint + step < n && a[hint + step] < *v {
+            last = hint + step;
+            step *= 2;
+        }
+        (last + 1, (hint + step).min(n))
+    } else {
+        // gallop left until a[hint - step] < v
+        let mut last = hint;
+        let mut step = 1;
+        while step <= hint && a[hint - step] >= *v {
+            last = hint - step;
+            step *= 2;
+        }
+        (hint.saturating_sub(step - 1), last)
+    };
+
+    let i = low + partition_point(&a[low..high], |x| x < v);
+    found(a, v, i)
+}
+
+#[inline]
+fn found<T: PartialOrd>(a: &[T], v: &T, i: usize) -> Result<usize, usize> {
+    if i < a.len() && a[i] == *v {
+        Ok(i)
+    } else {
+        Err(i)
+    }
+}
+
+#[inline]
+fn log2(n: usize) -> usize {
+    (usize::BITS - n.leading_zeros()) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{sorted_inputs, XorShift};
+
+    fn expected(a: &[u64], v: u64) -> Result<usize, usize> {
+        match a.iter().position(|&x| x >= v) {
+            Some(i) if a[i] == v => Ok(i),
+            Some(i) => Err(i),
+            None => Err(a.len()),
+        }
+    }
+
+    #[test]
+    fn test_against_linear_scan() {
+        let mut rng = XorShift(0x9a11);
+
+        for a in sorted_inputs() {
+            let max = a.last().cloned().unwrap_or(0) + 2;
+            for v in 0..max {
+                let e = expected(&a, v);
+                assert_eq!(exponential_search(&a, &v), e);
+                assert_eq!(interpolation_search(&a, &v), e);
+                assert_eq!(fibonacci_search(&a, &v), e);
+
+                let hint = rng.below(a.len() as u64 + 2) as usize;
+                assert_eq!(galloping_search(&a, &v, hint), e, "hint {}", hint);
+            }
+        }
+    }
+
+    #[test]
+    fn test_interpolation_search_skewed() {
+        // exponentially growing keys defeat interpolation
+        let a: Vec<u64> = (0..64).map(|i| 1 << i).collect();
+        for i in 0..64 {
+            assert_eq!(interpolation_search(&a, &(1 << i)), Ok(i));
+            assert_eq!(
+                interpolation_search(&a, &((1 << i) + 1)),
+                expected(&a, (1 << i) + 1)
+            );
+        }
+
+        let a = vec![i64::MIN, -1, 0, 1, i64::MAX];
+        for (i, v) in a.iter().enumerate() {
+            assert_eq!(interpolation_search(&a, v), Ok(i));
+        }
+
+        let a = vec![f64::NEG_INFINITY, -1.5, 0.0, 2.5, f64::INFINITY];
+        for (i, v) in a.iter().enumerate() {
+            assert_eq!(interpolation_search(&a, v), Ok(i));
+        }
+        assert_eq!(interpolation_search(&a, &1.0), Err(3));
+    }
+}
@@ -0,0 +1,517 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sets and maps stored as sorted vectors.
+//!
+//! Lookups are binary searches over contiguous memory, which beats tree-based
+//! collections for read-heavy use, at the cost of O(n) inserts and removals.
+
+use super::{lower_bound, upper_bound};
+use std::iter::{FromIterator, Zip};
+use std::ops::{Bound, Range, RangeBounds};
+use std::slice;
+use std::vec;
+
+/// A set of distinct values kept in a sorted `Vec`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::SortedVecSet;
+///
+/// let mut set: SortedVecSet<u32> = vec![5, 1, 3, 1].into_iter().collect();
+/// assert!(set.insert(4));
+/// assert!(!set.insert(5));
+/// assert_eq!(set.as_slice(), &[1, 3, 4, 5]);
+/// assert_eq!(set.range(2..5).cloned().collect::<Vec<_>>(), vec![3, 4]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SortedVecSet<T> {
+    items: Vec<T>,
+}
+
+impl<T: Ord> SortedVecSet<T> {
+    pub fn new() -> SortedVecSet<T> {
+        SortedVecSet { items: Vec::new() }
+    }
+
+    /// Takes `items` as they are if sorted and free of duplicates, otherwise
+    /// sorts and deduplicates them.
+    pub fn from_unsorted(mut items: Vec<T>) -> SortedVecSet<T> {
+        if !items.windows(2).all(|w| w[0] < w[1]) {
+            items.sort_unstable();
+            items.dedup();
+        }
+        SortedVecSet { items }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Adds `v`, returns `false` if it was already present.
+    pub fn insert(&mut self, v: T) -> bool {
+        let i = lower_bound(&self.items, &v);
+        if i < self.items.len() && self.items[i] == v {
+            return false;
+        }
+        self.items.insert(i, v);
+        true
+    }
+
+    /// Removes `v`, returns `false` if it was not present.
+    pub fn remove(&mut self, v: &T) -> bool {
+        match self.position(v) {
+            Some(i) => {
+                self.items.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn contains(&self, v: &T) -> bool {
+        self.position(v).is_some()
+    }
+
+    /// Returns the stored value equal to `v`.
+    pub fn get(&self, v: &T) -> Option<&T> {
+        self.position(v).map(|i| &self.items[i])
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.items.first()
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.items.last()
+    }
+
+    /// Iterates over the values in ascending order.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// Iterates over the values within `range` in ascending order.
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> slice::Iter<'_, T> {
+        self.items[range_indices(&self.items, range)].iter()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+
+    /// Values in `self` or `other`, merged in O(m + n).
+    pub fn union(&self, other: &SortedVecSet<T>) -> SortedVecSet<T>
+    where
+        T: Clone,
+    {
+        self.merge(other, true, true, true)
+    }
+
+    /// Values in both `self` and `other`, merged in O(m + n).
+    pub fn intersection(&self, other: &SortedVecSet<T>) -> SortedVecSet<T>
+    where
+        T: Clone,
+    {
+        self.merge(other, false, true, false)
+    }
+
+    /// Values in `self` but not in `other`, merged in O(m + n).
+    pub fn difference(&self, other: &SortedVecSet<T>) -> SortedVecSet<T>
+    where
+        T: Clone,
+    {
+        self.merge(other, true, false, false)
+    }
+
+    fn position(&self, v: &T) -> Option<usize> {
+        let i = lower_bound(&self.items, v);
+        if i < self.items.len() && self.items[i] == *v {
+            Some(i)
+        } else {
+            None
+        }
+    }
+
+    /// Walks both sets in order, keeping the values only in `self`, in both,
+    /// or only in `other` as requested.
+    fn merge(
+        &self,
+        other: &SortedVecSet<T>,
+        only_self: bool,
+        both: bool,
+        only_other: bool,
+    ) -> SortedVecSet<T>
+    where
+        T: Clone,
+    {
+        let (a, b) = (&self.items, &other.items);
+        let mut items = Vec::new();
+        let (mut i, mut j) = (0, 0);
+
+        while i < a.len() && j < b.len() {
+            if a[i] < b[j] {
+                if only_self {
+                    items.push(a[i].clone());
+                }
+                i += 1;
+            } else if b[j] < a[i] {
+                if only_other {
+                    items.push(b[j].clone());
+                }
+                j += 1;
+            } else {
+                if both {
+                    items.push(a[i].clone());
+                }
+                i += 1;
+                j += 1;
+            }
+        }
+
+        if only_self {
+            items.extend_from_slice(&a[i..]);
+        }
+        if only_other {
+            items.extend_from_slice(&b[j..]);
+        }
+
+        SortedVecSet { items }
+    }
+}
+
+impl<T: Ord> Default for SortedVecSet<T> {
+    fn default() -> SortedVecSet<T> {
+        SortedVecSet::new()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for SortedVecSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> SortedVecSet<T> {
+        SortedVecSet::from_unsorted(iter.into_iter().collect())
+    }
+}
+
+impl<T> IntoIterator for SortedVecSet<T> {
+    type Item = T;
+    type IntoIter = vec::IntoIter<T>;
+
+    fn into_iter(self) -> vec::IntoIter<T> {
+        self.items.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SortedVecSet<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> slice::Iter<'a, T> {
+        self.items.iter()
+    }
+}
+
+/// A map kept as a sorted `Vec` of keys and a parallel `Vec` of values.
+///
+/// # Examples
+///
+/// ```
+/// extern crate binary_search;
+/// use binary_search::SortedVecMap;
+///
+/// let mut map: SortedVecMap<u32, &str> = vec![(3, "c"), (1, "a")].into_iter().collect();
+/// assert_eq!(map.insert(2, "b"), None);
+/// assert_eq!(map.insert(3, "C"), Some("c"));
+/// assert_eq!(map.get(&3), Some(&"C"));
+/// let keys: Vec<u32> = map.range(2..).map(|(k, _)| *k).collect();
+/// assert_eq!(keys, vec![2, 3]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SortedVecMap<K, V> {
+    keys: Vec<K>,
+    values: Vec<V>,
+}
+
+impl<K: Ord, V> SortedVecMap<K, V> {
+    pub fn new() -> SortedVecMap<K, V> {
+        SortedVecMap {
+            keys: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Builds the map from pairs in any order; for repeated keys the value
+    /// that comes last wins, as if inserted one by one.
+    pub fn from_unsorted(pairs: Vec<(K, V)>) -> SortedVecMap<K, V> {
+        // sort the keys with their positions so that equal keys keep their
+        // order, and only move the values once
+        let mut values = Vec::with_capacity(pairs.len());
+        let mut order: Vec<(K, usize)> = Vec::with_capacity(pairs.len());
+        for (i, (k, v)) in pairs.into_iter().enumerate() {
+            order.push((k, i));
+            values.push(Some(v));
+        }
+        order.sort_unstable();
+
+        let mut map = SortedVecMap::new();
+        let mut order = order.into_iter().peekable();
+        while let Some((k, i)) = order.next() {
+            if order.peek().is_none_or(|next| next.0 != k) {
+                map.keys.push(k);
+                map.values.push(values[i].take().unwrap());
+            }
+        }
+
+        map
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Inserts or replaces the value of `k`, returns the old value.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let i = lower_bound(&self.keys, &k);
+        if i < self.keys.len() && self.keys[i] == k {
+            return Some(std::mem::replace(&mut self.values[i], v));
+        }
+        self.keys.insert(i, k);
+        self.values.insert(i, v);
+        None
+    }
+
+    /// Removes `k`, returns its value.
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        self.position(k).map(|i| {
+            self.keys.remove(i);
+            self.values.remove(i)
+        })
+    }
+
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.position(k).is_some()
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.position(k).map(|i| &self.values[i])
+    }
+
+    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        match self.position(k) {
+            Some(i) => Some(&mut self.values[i]),
+            None => None,
+        }
+    }
+
+    /// Iterates over the entries in ascending order of keys.
+    pub fn iter(&self) -> Zip<slice::Iter<'_, K>, slice::Iter<'_, V>> {
+        self.keys.iter().zip(self.values.iter())
+    }
+
+    /// Iterates over the entries whose keys are within `range`, in ascending
+    /// order of keys.
+    pub fn range<R: RangeBounds<K>>(
+        &self,
+        range: R,
+    ) -> Zip<slice::Iter<'_, K>, slice::Iter<'_, V>> {
+        let r = range_indices(&self.keys, range);
+        self.keys[r.clone()].iter().zip(self.values[r].iter())
+    }
+
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+
+    pub fn values(&self) -> &[V] {
+        &self.values
+    }
+
+    fn position(&self, k: &K) -> Option<usize> {
+        let i = lower_bound(&self.keys, k);
+        if i < self.keys.len() && self.keys[i] == *k {
+            Some(i)
+        } else {
+            None
+        }
+    }
+}
+
+impl<K: Ord, V> Default for SortedVecMap<K, V> {
+    fn default() -> SortedVecMap<K, V> {
+        SortedVecMap::new()
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SortedVecMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> SortedVecMap<K, V> {
+        SortedVecMap::from_unsorted(iter.into_iter().collect())
+    }
+}
+
+impl<K, V> IntoIterator for SortedVecMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = Zip<vec::IntoIter<K>, vec::IntoIter<V>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.keys.into_iter().zip(self.values)
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SortedVecMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Zip<slice::Iter<'a, K>, slice::Iter<'a, V>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.keys.iter().zip(self.values.iter())
+    }
+}
+
+/// Indices of the elements of the sorted `a` within `range`, empty if the
+/// range is backwards.
+fn range_indices<T: Ord, R: RangeBounds<T>>(a: &[T], range: R) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(v) => lower_bound(a, v),
+        Bound::Excluded(v) => upper_bound(a, v),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(v) => upper_bound(a, v),
+        Bound::Excluded(v) => lower_bound(a, v),
+        Bound::Unbounded => a.len(),
+    };
+    start..end.max(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap, BTreeSet};
+    use testing::XorShift;
+
+    #[test]
+    fn test_sorted_vec_set() {
+        let mut rng = XorShift(0x5e7);
+        let mut set = SortedVecSet::new();
+        let mut expected = BTreeSet::new();
+
+        for _ in 0..2000 {
+            let v = rng.below(100);
+            match rng.below(3) {
+                0 | 1 => assert_eq!(set.insert(v), expected.insert(v)),
+                _ => assert_eq!(set.remove(&v), expected.remove(&v)),
+            }
+            assert_eq!(set.contains(&v), expected.contains(&v));
+        }
+
+        assert_eq!(set.len(), expected.len());
+        assert!(set.iter().eq(expected.iter()));
+        assert_eq!(set.first(), expected.iter().next());
+        assert_eq!(set.last(), expected.iter().next_back());
+
+        for &(lo, hi) in &[(0, 100), (10, 20), (50, 50), (99, 150)] {
+            assert!(set.range(lo..hi).eq(expected.range(lo..hi)));
+            assert!(set.range(lo..=hi).eq(expected.range(lo..=hi)));
+            assert!(set.range(lo..).eq(expected.range(lo..)));
+            assert!(set.range(..hi).eq(expected.range(..hi)));
+        }
+        assert_eq!(
+            set.range((Bound::Included(20), Bound::Excluded(10)))
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a: SortedVecSet<u32> = vec![1, 3, 5, 7, 9, 3].into_iter().collect();
+        let b: SortedVecSet<u32> = vec![9, 2, 3, 4, 5].into_iter().collect();
+
+        assert_eq!(a.as_slice(), &[1, 3, 5, 7, 9]);
+        assert_eq!(a.union(&b).as_slice(), &[1, 2, 3, 4, 5, 7, 9]);
+        assert_eq!(a.intersection(&b).as_slice(), &[3, 5, 9]);
+        assert_eq!(a.difference(&b).as_slice(), &[1, 7]);
+        assert_eq!(b.difference(&a).as_slice(), &[2, 4]);
+
+        let empty = SortedVecSet::new();
+        assert_eq!(a.union(&empty), a);
+        assert!(a.intersection(&empty).is_empty());
+        assert_eq!(a.difference(&empty), a);
+    }
+
+    #[test]
+    fn test_sorted_vec_map() {
+        let mut rng = XorShift(0x3a9);
+        let mut map = SortedVecMap::new();
+        let mut expected = BTreeMap::new();
+
+        for i in 0..2000 {
+            let k = rng.below(100);
+            match rng.below(3) {
+                0 | 1 => assert_eq!(map.insert(k, i), expected.insert(k, i)),
+                _ => assert_eq!(map.remove(&k), expected.remove(&k)),
+            }
+            assert_eq!(map.get(&k), expected.get(&k));
+        }
+
+        if let Some(v) = map.get_mut(&42) {
+            *v = 0;
+            *expected.get_mut(&42).unwrap() = 0;
+        }
+
+        assert_eq!(map.len(), expected.len());
+        assert!(map.iter().eq(expected.iter()));
+        assert!(map.range(10..20).eq(expected.range(10..20)));
+        assert!(map.range(..=50).eq(expected.range(..=50)));
+        assert!(map.into_iter().eq(expected.into_iter()));
+    }
+
+    #[test]
+    fn test_map_from_unsorted() {
+        let pairs = vec![(3, 'a'), (1, 'b'), (3, 'c'), (2, 'd'), (1, 'e'), (3, 'f')];
+        let map: SortedVecMap<u32, char> = pairs.iter().cloned().collect();
+        let expected: BTreeMap<u32, char> = pairs.into_iter().collect();
+        assert!(map.iter().eq(expected.iter()));
+        assert_eq!(map.keys(), &[1, 2, 3]);
+        assert_eq!(map.values(), &['e', 'd', 'f']);
+    }
+
+    #[test]
+    fn test_non_copy_keys() {
+        let words = ["pear", "fig", "apple", "fig"];
+        let set: SortedVecSet<String> = words.iter().map(|w| w.to_string()).collect();
+        assert_eq!(set.as_slice(), &["apple", "fig", "pear"]);
+        assert!(set.contains(&"fig".to_string()));
+
+        let map: SortedVecMap<String, usize> = words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| (w.to_string(), i))
+            .collect();
+        assert_eq!(map.keys(), &["apple", "fig", "pear"]);
+        assert_eq!(map.values(), &[2, 3, 0]);
+    }
+}
@@ -0,0 +1,44 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers shared by the unit tests of this crate.
+
+pub struct XorShift(pub u64);
+
+impl XorShift {
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % n
+    }
+}
+
+/// Random sorted slices of every length up to 64, with many duplicates,
+/// few duplicates or none.
+pub fn sorted_inputs() -> Vec<Vec<u64>> {
+    let mut rng = XorShift(0x5eed);
+    let mut inputs = Vec::new();
+
+    for n in 0..65 {
+        for &range in &[4, 1000] {
+            let mut a: Vec<u64> = (0..n).map(|_| rng.below(range)).collect();
+            a.sort();
+            inputs.push(a);
+        }
+        inputs.push((0..n).map(|x| 2 * x + 1).collect());
+    }
+
+    inputs
+}
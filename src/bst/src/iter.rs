@@ -0,0 +1,190 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Traversals of a `BstMap`.
+//!
+//! The depth-first ones keep the pending nodes on a `LinkedStack`, the
+//! level-order one on a `LinkedQueue`, so none of them recurses.
+
+use super::{after_start, before_end, Link, Node};
+use queue::{LinkedQueue, Queue};
+use stack::{LinkedStack, Stack};
+use std::ops::{RangeBounds, RangeFull};
+
+#[inline]
+fn entry<K, V>(node: &Node<K, V>) -> (&K, &V) {
+    (&node.key, &node.value)
+}
+
+/// Entries of a `BstMap` with keys in a range, in ascending key order.
+pub struct Range<'a, K: 'a, V: 'a, R> {
+    /// Nodes whose left subtree has been visited or skipped, the next one on
+    /// top.
+    stack: LinkedStack<&'a Node<K, V>>,
+    range: R,
+}
+
+impl<'a, K: Ord, V, R: RangeBounds<K>> Range<'a, K, V, R> {
+    pub(crate) fn new(root: &'a Link<K, V>, range: R) -> Range<'a, K, V, R> {
+        // the path to the first key in range, minus the nodes before it
+        let mut stack = LinkedStack::new();
+        let mut link = root;
+        while let Some(ref node) = *link {
+            if after_start(&range, &node.key) {
+                stack.push(&**node);
+                link = &node.left;
+            } else {
+                link = &node.right;
+            }
+        }
+        Range { stack, range }
+    }
+}
+
+impl<'a, K: Ord, V, R: RangeBounds<K>> Iterator for Range<'a, K, V, R> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let node = self.stack.pop()?;
+        if !before_end(&self.range, &node.key) {
+            self.stack = LinkedStack::new();
+            return None;
+        }
+
+        let mut link = &node.right;
+        while let Some(ref child) = *link {
+            self.stack.push(&**child);
+            link = &child.left;
+        }
+        Some(entry(node))
+    }
+}
+
+/// Entries of a `BstMap` in ascending key order.
+pub struct Iter<'a, K: 'a, V: 'a> {
+    range: Range<'a, K, V, RangeFull>,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    pub(crate) fn new(range: Range<'a, K, V, RangeFull>) -> Iter<'a, K, V> {
+        Iter { range }
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        self.range.next()
+    }
+}
+
+/// Entries of a `BstMap` with every node before its subtrees.
+pub struct PreOrder<'a, K: 'a, V: 'a> {
+    stack: LinkedStack<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> PreOrder<'a, K, V> {
+    pub(crate) fn new(root: &'a Link<K, V>) -> PreOrder<'a, K, V> {
+        let mut stack = LinkedStack::new();
+        if let Some(ref root) = *root {
+            stack.push(&**root);
+        }
+        PreOrder { stack }
+    }
+}
+
+impl<'a, K, V> Iterator for PreOrder<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let node = self.stack.pop()?;
+        // right first, so that the left subtree comes out first
+        if let Some(ref right) = node.right {
+            self.stack.push(&**right);
+        }
+        if let Some(ref left) = node.left {
+            self.stack.push(&**left);
+        }
+        Some(entry(node))
+    }
+}
+
+/// Entries of a `BstMap` with every node after its subtrees.
+pub struct PostOrder<'a, K: 'a, V: 'a> {
+    /// Pending nodes, with whether their subtrees have been pushed already.
+    stack: LinkedStack<(&'a Node<K, V>, bool)>,
+}
+
+impl<'a, K, V> PostOrder<'a, K, V> {
+    pub(crate) fn new(root: &'a Link<K, V>) -> PostOrder<'a, K, V> {
+        let mut stack = LinkedStack::new();
+        if let Some(ref root) = *root {
+            stack.push((&**root, false));
+        }
+        PostOrder { stack }
+    }
+}
+
+impl<'a, K, V> Iterator for PostOrder<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            let (node, expanded) = self.stack.pop()?;
+            if expanded {
+                return Some(entry(node));
+            }
+
+            self.stack.push((node, true));
+            if let Some(ref right) = node.right {
+                self.stack.push((&**right, false));
+            }
+            if let Some(ref left) = node.left {
+                self.stack.push((&**left, false));
+            }
+        }
+    }
+}
+
+/// Entries of a `BstMap` level by level from the root, each level left to
+/// right.
+pub struct LevelOrder<'a, K: 'a, V: 'a> {
+    queue: LinkedQueue<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> LevelOrder<'a, K, V> {
+    pub(crate) fn new(root: &'a Link<K, V>) -> LevelOrder<'a, K, V> {
+        let mut queue = LinkedQueue::new();
+        if let Some(ref root) = *root {
+            queue.enqueue(&**root);
+        }
+        LevelOrder { queue }
+    }
+}
+
+impl<'a, K, V> Iterator for LevelOrder<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let node = self.queue.dequeue()?;
+        if let Some(ref left) = node.left {
+            self.queue.enqueue(&**left);
+        }
+        if let Some(ref right) = node.right {
+            self.queue.enqueue(&**right);
+        }
+        Some(entry(node))
+    }
+}
@@ -0,0 +1,566 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An ordered map on an unbalanced binary search tree.
+//!
+//! Every node records the size of its subtree, which gives `rank` and
+//! `select` in O(height). Nothing keeps the tree balanced: random keys give
+//! O(log n) height, keys inserted in order give a list. All operations walk
+//! the tree iteratively, so a degenerate tree costs time but never overflows
+//! the call stack.
+//!
+//! reference:
+//! https://algs4.cs.princeton.edu/32bst/
+
+extern crate queue;
+extern crate stack;
+
+use stack::{LinkedStack, Stack};
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+
+mod iter;
+
+pub use iter::{Iter, LevelOrder, PostOrder, PreOrder, Range};
+
+#[cfg(test)]
+mod testing;
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    left: Link<K, V>,
+    right: Link<K, V>,
+    /// Number of nodes in the subtree rooted here.
+    size: usize,
+}
+
+impl<K, V> Node<K, V> {
+    fn new(key: K, value: V) -> Node<K, V> {
+        Node {
+            key,
+            value,
+            left: None,
+            right: None,
+            size: 1,
+        }
+    }
+}
+
+#[inline]
+fn size<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+/// Removes the node with the smallest key from the non-empty subtree at
+/// `link`.
+fn take_min<K, V>(mut link: &mut Link<K, V>) -> Box<Node<K, V>> {
+    while link.as_ref().unwrap().left.is_some() {
+        let node = link.as_mut().unwrap();
+        node.size -= 1;
+        link = &mut node.left;
+    }
+
+    let mut min = link.take().unwrap();
+    *link = min.right.take();
+    min
+}
+
+/// Whether `key` is not below the start of `range`.
+fn after_start<K: Ord, R: RangeBounds<K>>(range: &R, key: &K) -> bool {
+    match range.start_bound() {
+        Bound::Included(start) => key >= start,
+        Bound::Excluded(start) => key > start,
+        Bound::Unbounded => true,
+    }
+}
+
+/// Whether `key` is not above the end of `range`.
+fn before_end<K: Ord, R: RangeBounds<K>>(range: &R, key: &K) -> bool {
+    match range.end_bound() {
+        Bound::Included(end) => key <= end,
+        Bound::Excluded(end) => key < end,
+        Bound::Unbounded => true,
+    }
+}
+
+/// An ordered map on an unbalanced binary search tree.
+///
+/// # Examples
+///
+/// ```
+/// extern crate bst;
+/// use bst::BstMap;
+///
+/// let mut map = BstMap::new();
+/// map.insert(5, "five");
+/// map.insert(2, "two");
+/// map.insert(8, "eight");
+/// assert_eq!(map.insert(2, "deux"), Some("two"));
+///
+/// assert_eq!(map.get(&2), Some(&"deux"));
+/// assert_eq!(map.floor(&7), Some((&5, &"five")));
+/// assert_eq!(map.rank(&8), 2);
+/// assert_eq!(map.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec![2, 5, 8]);
+///
+/// assert_eq!(map.remove(&5), Some("five"));
+/// assert_eq!(map.len(), 2);
+/// ```
+pub struct BstMap<K, V> {
+    root: Link<K, V>,
+}
+
+impl<K: Ord, V> BstMap<K, V> {
+    pub fn new() -> BstMap<K, V> {
+        BstMap { root: None }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        *self = BstMap::new();
+    }
+
+    /// Number of nodes on the longest path from the root, 0 if empty.
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut stack = LinkedStack::new();
+        if let Some(ref root) = self.root {
+            stack.push((&**root, 1));
+        }
+        while let Some((node, depth)) = stack.pop() {
+            height = height.max(depth);
+            for child in [&node.left, &node.right].iter() {
+                if let Some(ref child) = **child {
+                    stack.push((&**child, depth + 1));
+                }
+            }
+        }
+        height
+    }
+
+    fn find(&self, k: &K) -> Option<&Node<K, V>> {
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            link = match k.cmp(&node.key) {
+                Ordering::Less => &node.left,
+                Ordering::Greater => &node.right,
+                Ordering::Equal => return Some(node),
+            };
+        }
+        None
+    }
+
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.find(k).is_some()
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.find(k).map(|node| &node.value)
+    }
+
+    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        let mut link = &mut self.root;
+        while let Some(node) = link {
+            link = match k.cmp(&node.key) {
+                Ordering::Less => &mut node.left,
+                Ordering::Greater => &mut node.right,
+                Ordering::Equal => return Some(&mut node.value),
+            };
+        }
+        None
+    }
+
+    /// Inserts `v` under `k`, returning the value it replaces if `k` was
+    /// already present.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        if let Some(old) = self.get_mut(&k) {
+            return Some(mem::replace(old, v));
+        }
+
+        // the key is new, so every node on the way gains a descendant
+        let mut link = &mut self.root;
+        while let Some(node) = link {
+            node.size += 1;
+            link = if k < node.key {
+                &mut node.left
+            } else {
+                &mut node.right
+            };
+        }
+        *link = Some(Box::new(Node::new(k, v)));
+        None
+    }
+
+    /// Removes `k`, returning its value if it was present.
+    ///
+    /// A node with two children is replaced by the smallest node of its
+    /// right subtree (Hibbard deletion).
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        if !self.contains_key(k) {
+            return None;
+        }
+
+        // the key is present, so every node on the way loses a descendant
+        let mut link = &mut self.root;
+        loop {
+            let order = k.cmp(&link.as_ref().unwrap().key);
+            if order == Ordering::Equal {
+                break;
+            }
+            let node = link.as_mut().unwrap();
+            node.size -= 1;
+            link = if order == Ordering::Less {
+                &mut node.left
+            } else {
+                &mut node.right
+            };
+        }
+
+        let mut node = link.take().unwrap();
+        *link = match (node.left.take(), node.right.take()) {
+            (None, child) | (child, None) => child,
+            (left, mut right) => {
+                let mut min = take_min(&mut right);
+                min.left = left;
+                min.right = right;
+                min.size = node.size - 1;
+                Some(min)
+            }
+        };
+        Some(node.value)
+    }
+
+    /// The entry with the smallest key.
+    pub fn min(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref()?;
+        while let Some(ref left) = node.left {
+            node = left;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    /// The entry with the largest key.
+    pub fn max(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref()?;
+        while let Some(ref right) = node.right {
+            node = right;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    /// The entry with the largest key less than or equal to `k`.
+    pub fn floor(&self, k: &K) -> Option<(&K, &V)> {
+        let mut best = None;
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            match k.cmp(&node.key) {
+                Ordering::Less => link = &node.left,
+                Ordering::Greater => {
+                    best = Some(node);
+                    link = &node.right;
+                }
+                Ordering::Equal => return Some((&node.key, &node.value)),
+            }
+        }
+        best.map(|node| (&node.key, &node.value))
+    }
+
+    /// The entry with the smallest key greater than or equal to `k`.
+    pub fn ceiling(&self, k: &K) -> Option<(&K, &V)> {
+        let mut best = None;
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            match k.cmp(&node.key) {
+                Ordering::Less => {
+                    best = Some(node);
+                    link = &node.left;
+                }
+                Ordering::Greater => link = &node.right,
+                Ordering::Equal => return Some((&node.key, &node.value)),
+            }
+        }
+        best.map(|node| (&node.key, &node.value))
+    }
+
+    /// Number of keys less than `k`.
+    pub fn rank(&self, k: &K) -> usize {
+        let mut rank = 0;
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            match k.cmp(&node.key) {
+                Ordering::Less => link = &node.left,
+                Ordering::Greater => {
+                    rank += size(&node.left) + 1;
+                    link = &node.right;
+                }
+                Ordering::Equal => return rank + size(&node.left),
+            }
+        }
+        rank
+    }
+
+    /// The entry with the `i`-th smallest key, counting from 0.
+    pub fn select(&self, mut i: usize) -> Option<(&K, &V)> {
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            let left = size(&node.left);
+            match i.cmp(&left) {
+                Ordering::Less => link = &node.left,
+                Ordering::Greater => {
+                    i -= left + 1;
+                    link = &node.right;
+                }
+                Ordering::Equal => return Some((&node.key, &node.value)),
+            }
+        }
+        None
+    }
+
+    /// Entries in ascending key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter::new(self.range(..))
+    }
+
+    /// Entries with keys in `range`, in ascending key order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bst;
+    /// use bst::BstMap;
+    ///
+    /// let map: BstMap<u32, ()> = (0..10).map(|k| (k * 10, ())).collect();
+    /// let keys: Vec<u32> = map.range(25..=60).map(|(k, _)| *k).collect();
+    /// assert_eq!(keys, vec![30, 40, 50, 60]);
+    /// ```
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V, R> {
+        Range::new(&self.root, range)
+    }
+
+    /// Entries with every node before its subtrees, left before right.
+    pub fn pre_order(&self) -> PreOrder<'_, K, V> {
+        PreOrder::new(&self.root)
+    }
+
+    /// Entries with every node after its subtrees, left before right.
+    pub fn post_order(&self) -> PostOrder<'_, K, V> {
+        PostOrder::new(&self.root)
+    }
+
+    /// Entries level by level from the root, each level left to right.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bst;
+    /// use bst::BstMap;
+    ///
+    /// let map: BstMap<u32, ()> = [4, 2, 6, 1, 3, 5, 7].iter().map(|&k| (k, ())).collect();
+    /// let keys: Vec<u32> = map.level_order().map(|(k, _)| *k).collect();
+    /// assert_eq!(keys, vec![4, 2, 6, 1, 3, 5, 7]);
+    /// ```
+    pub fn level_order(&self) -> LevelOrder<'_, K, V> {
+        LevelOrder::new(&self.root)
+    }
+}
+
+impl<K: Ord, V> Default for BstMap<K, V> {
+    fn default() -> BstMap<K, V> {
+        BstMap::new()
+    }
+}
+
+impl<K, V> Drop for BstMap<K, V> {
+    /// Frees the nodes one by one, as dropping a degenerate tree
+    /// recursively could overflow the stack.
+    fn drop(&mut self) {
+        let mut stack = LinkedStack::new();
+        if let Some(root) = self.root.take() {
+            stack.push(root);
+        }
+        while let Some(mut node) = stack.pop() {
+            if let Some(left) = node.left.take() {
+                stack.push(left);
+            }
+            if let Some(right) = node.right.take() {
+                stack.push(right);
+            }
+        }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for BstMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> BstMap<K, V> {
+        let mut map = BstMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for BstMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a BstMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use testing::XorShift;
+
+    fn keys<'a, I: Iterator<Item = (&'a u32, &'a u32)>>(iter: I) -> Vec<u32> {
+        iter.map(|(k, _)| *k).collect()
+    }
+
+    /// Checks the ordering and the subtree sizes of every node.
+    fn check_invariants(map: &BstMap<u32, u32>) {
+        fn check(link: &Link<u32, u32>, lo: Option<u32>, hi: Option<u32>) -> usize {
+            match *link {
+                None => 0,
+                Some(ref node) => {
+                    assert!(lo.is_none_or(|lo| lo < node.key));
+                    assert!(hi.is_none_or(|hi| node.key < hi));
+                    let size = 1
+                        + check(&node.left, lo, Some(node.key))
+                        + check(&node.right, Some(node.key), hi);
+                    assert_eq!(node.size, size);
+                    size
+                }
+            }
+        }
+        check(&map.root, None, None);
+    }
+
+    #[test]
+    fn test_bst_map() {
+        let mut map = BstMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.remove(&1), None);
+        assert_eq!(map.min(), None);
+        assert_eq!(map.height(), 0);
+
+        assert_eq!(map.insert(3, 30), None);
+        assert_eq!(map.insert(1, 10), None);
+        assert_eq!(map.insert(3, 31), Some(30));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.height(), 2);
+
+        *map.get_mut(&1).unwrap() += 1;
+        assert_eq!(map.get(&1), Some(&11));
+        assert_eq!(map.min(), Some((&1, &11)));
+        assert_eq!(map.max(), Some((&3, &31)));
+
+        map.clear();
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_bst_map_random() {
+        let mut rng = XorShift(0x50);
+        let mut map = BstMap::new();
+        let mut expected = BTreeMap::new();
+
+        for round in 0..3000 {
+            let k = rng.below(200) as u32 * 2;
+            if rng.below(3) == 0 {
+                assert_eq!(map.remove(&k), expected.remove(&k));
+            } else {
+                assert_eq!(map.insert(k, round), expected.insert(k, round));
+            }
+            assert_eq!(map.len(), expected.len());
+
+            if round % 100 != 0 {
+                continue;
+            }
+            check_invariants(&map);
+            assert!(map.iter().eq(expected.iter()));
+
+            for q in 0..402 {
+                assert_eq!(map.get(&q), expected.get(&q));
+                assert_eq!(map.floor(&q), expected.range(..=q).next_back());
+                assert_eq!(map.ceiling(&q), expected.range(q..).next());
+                assert_eq!(map.rank(&q), expected.range(..q).count());
+            }
+            for i in 0..expected.len() + 1 {
+                assert_eq!(map.select(i), expected.iter().nth(i));
+            }
+
+            let lo = rng.below(400) as u32;
+            let hi = lo + rng.below(100) as u32;
+            assert!(map.range(lo..hi).eq(expected.range(lo..hi)));
+            assert!(map.range(lo..=hi).eq(expected.range(lo..=hi)));
+            assert!(map.range(..hi).eq(expected.range(..hi)));
+            let excluded = (Bound::Excluded(lo), Bound::Unbounded);
+            assert!(map.range(excluded).eq(expected.range(excluded)));
+        }
+    }
+
+    #[test]
+    fn test_bst_map_traversals() {
+        //       4
+        //     2   6
+        //    1 3 5 7
+        let map: BstMap<u32, u32> = [4, 2, 6, 1, 3, 5, 7].iter().map(|&k| (k, k)).collect();
+        check_invariants(&map);
+        assert_eq!(map.height(), 3);
+        assert_eq!(keys(map.iter()), vec![1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(keys(map.pre_order()), vec![4, 2, 1, 3, 6, 5, 7]);
+        assert_eq!(keys(map.post_order()), vec![1, 3, 2, 5, 7, 6, 4]);
+        assert_eq!(keys(map.level_order()), vec![4, 2, 6, 1, 3, 5, 7]);
+
+        let empty: BstMap<u32, u32> = BstMap::new();
+        assert_eq!(empty.iter().count(), 0);
+        assert_eq!(empty.pre_order().count(), 0);
+        assert_eq!(empty.post_order().count(), 0);
+        assert_eq!(empty.level_order().count(), 0);
+    }
+
+    #[test]
+    fn test_bst_map_degenerate() {
+        // keys in order make a list as deep as the map is long
+        let n = 10_000;
+        let mut map: BstMap<u32, u32> = (0..n).map(|k| (k, k)).collect();
+        assert_eq!(map.height(), n as usize);
+        assert_eq!(map.select(n as usize - 1), Some((&(n - 1), &(n - 1))));
+        assert_eq!(map.post_order().next(), Some((&(n - 1), &(n - 1))));
+        assert_eq!(map.remove(&0), Some(0));
+        assert_eq!(map.len(), n as usize - 1);
+    }
+}
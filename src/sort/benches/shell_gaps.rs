@@ -0,0 +1,103 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compares the shell sort gap sequences across input distributions.
+//!
+//! Run with `cargo bench --bench shell_gaps`, optionally passing the slice
+//! length (default 100000).
+
+extern crate sort;
+
+use sort::gaps::{Ciura, GapSequence, Hibbard, Knuth, Pratt, Sedgewick, Shell, Tokuda};
+use sort::instrument::{self, SortStats};
+use sort::shell_sort_with;
+use std::env;
+use std::time::{Duration, Instant};
+
+const ROUNDS: usize = 5;
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+fn distributions(n: usize) -> Vec<(&'static str, Vec<u32>)> {
+    let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+
+    let random = (0..n).map(|_| rng.next() as u32).collect();
+    let few_unique = (0..n).map(|_| (rng.next() % 16) as u32).collect();
+    let sorted = (0..n as u32).collect();
+    let reversed = (0..n as u32).rev().collect();
+    let mut nearly_sorted: Vec<u32> = (0..n as u32).collect();
+    for _ in 0..n / 100 {
+        let i = rng.next() as usize % n;
+        let j = rng.next() as usize % n;
+        nearly_sorted.swap(i, j);
+    }
+
+    vec![
+        ("random", random),
+        ("few unique", few_unique),
+        ("sorted", sorted),
+        ("reversed", reversed),
+        ("nearly sorted", nearly_sorted),
+    ]
+}
+
+fn bench<G: GapSequence + Copy>(name: &str, seq: G, input: &[u32]) {
+    let mut best = Duration::from_secs(u64::MAX);
+    for _ in 0..ROUNDS {
+        let mut v = input.to_vec();
+        let start = Instant::now();
+        shell_sort_with(&mut v, seq);
+        best = best.min(start.elapsed());
+    }
+
+    let mut stats = SortStats::default();
+    instrument::shell_sort_with(&mut input.to_vec(), seq, &mut stats);
+
+    println!(
+        "  {:<10} {:>10.3} ms {:>14} cmp {:>14} writes",
+        name,
+        best.as_secs_f64() * 1e3,
+        stats.comparisons,
+        stats.writes
+    );
+}
+
+fn main() {
+    let n = env::args()
+        .skip(1)
+        .find(|arg| !arg.starts_with('-'))
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(100_000);
+
+    println!("shell sort, n = {}, best of {} rounds", n, ROUNDS);
+    for (name, input) in distributions(n) {
+        println!("{}:", name);
+        bench("knuth", Knuth, &input);
+        bench("shell", Shell, &input);
+        bench("hibbard", Hibbard, &input);
+        bench("sedgewick", Sedgewick, &input);
+        bench("tokuda", Tokuda, &input);
+        bench("ciura", Ciura, &input);
+        bench("pratt", Pratt, &input);
+    }
+}
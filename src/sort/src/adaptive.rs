@@ -0,0 +1,327 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sortedness checks, measures of presortedness and a sort that dispatches on
+//! them.
+
+use super::{insertion_sort, lt, quick_sort, tim_sort, INSERTION_SORT_THRESHOLD};
+use std::cmp::Ordering;
+
+// inputs with at least this many elements per run on average are merged
+const MIN_AVERAGE_RUN: usize = 32;
+
+/// Returns `true` if `a` is in non-decreasing order.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::is_sorted;
+///
+/// assert!(is_sorted(&[1, 2, 2, 3]));
+/// assert!(!is_sorted(&[1, 3, 2]));
+/// ```
+pub fn is_sorted<T: PartialOrd>(a: &[T]) -> bool {
+    a.windows(2).all(|w| !lt(&w[1], &w[0]))
+}
+
+/// Returns `true` if no element of `a` compares `Greater` than the next one
+/// according to `compare`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::is_sorted_by;
+///
+/// assert!(is_sorted_by(&[3, 2, 2, 1], |a, b| b.cmp(a)));
+/// ```
+pub fn is_sorted_by<T, F>(a: &[T], mut compare: F) -> bool
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    a.windows(2)
+        .all(|w| compare(&w[0], &w[1]) != Ordering::Greater)
+}
+
+/// Number of pairs `i < j` with `a[i] > a[j]`, counted while merge sorting a
+/// copy of `a` in O(n log n).
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::inversions;
+///
+/// assert_eq!(inversions(&[1, 2, 3]), 0);
+/// assert_eq!(inversions(&[3, 1, 2]), 2);
+/// assert_eq!(inversions(&[3, 2, 1]), 3);
+/// ```
+pub fn inversions<T: PartialOrd + Copy>(a: &[T]) -> u64 {
+    let mut v = a.to_vec();
+    let mut buf = a.to_vec();
+    count_inversions(&mut v, &mut buf)
+}
+
+fn count_inversions<T: PartialOrd + Copy>(a: &mut [T], buf: &mut [T]) -> u64 {
+    let n = a.len();
+
+    if n <= 1 {
+        return 0;
+    }
+
+    let mid = n / 2;
+    let mut count = {
+        let (a_left, a_right) = a.split_at_mut(mid);
+        let (buf_left, buf_right) = buf.split_at_mut(mid);
+        count_inversions(a_left, buf_left) + count_inversions(a_right, buf_right)
+    };
+
+    buf.copy_from_slice(a);
+    let (left, right) = buf.split_at(mid);
+    let (mut i, mut j) = (0, 0);
+    for x in a.iter_mut() {
+        if j == right.len() || (i < left.len() && !lt(&right[j], &left[i])) {
+            *x = left[i];
+            i += 1;
+        } else {
+            // right[j] is smaller than everything left in `left`
+            *x = right[j];
+            j += 1;
+            count += (left.len() - i) as u64;
+        }
+    }
+
+    count
+}
+
+/// Number of maximal non-decreasing runs in `a`, `0` if it is empty.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::runs;
+///
+/// assert_eq!(runs(&[1, 2, 3]), 1);
+/// assert_eq!(runs(&[1, 3, 2, 4, 0]), 3);
+/// ```
+pub fn runs<T: PartialOrd>(a: &[T]) -> usize {
+    if a.is_empty() {
+        return 0;
+    }
+
+    1 + a.windows(2).filter(|w| lt(&w[1], &w[0])).count()
+}
+
+/// Length of the longest non-decreasing subsequence of `a`, so `a.len()`
+/// minus it is the least number of elements to move to sort `a`.
+///
+/// Uses patience sorting with binary search, O(n log n).
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::longest_increasing_subsequence;
+///
+/// assert_eq!(longest_increasing_subsequence(&[3, 1, 2, 2, 5, 4]), 4);
+/// ```
+pub fn longest_increasing_subsequence<T: PartialOrd + Copy>(a: &[T]) -> usize {
+    // tails[k] is the smallest last element of a subsequence of length k + 1
+    let mut tails: Vec<T> = Vec::new();
+
+    for &x in a {
+        let k = tails.partition_point(|t| !lt(&x, t));
+        if k == tails.len() {
+            tails.push(x);
+        } else {
+            tails[k] = x;
+        }
+    }
+
+    tails.len()
+}
+
+// inputs with at most `n / MAX_DISPLACED_RATIO` elements outside a longest
+// increasing subsequence are merged even when they have many runs
+const MAX_DISPLACED_RATIO: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Choice {
+    Sorted,
+    Insertion,
+    Merge,
+    Quick,
+}
+
+/// Picks an algorithm from the runs, the inversions and the longest
+/// increasing subsequence of `a`.
+fn choose<T: PartialOrd + Copy>(a: &[T]) -> Choice {
+    let n = a.len();
+
+    let runs = runs(a);
+    if runs <= 1 {
+        return Choice::Sorted;
+    }
+
+    // insertion sort moves each element once per inversion it is part of
+    if inversions(a) <= n as u64 {
+        return Choice::Insertion;
+    }
+
+    if runs <= n / MIN_AVERAGE_RUN {
+        return Choice::Merge;
+    }
+
+    // a few misplaced elements cut the runs short, but merging still finds
+    // the ordered stretches between them
+    if n - longest_increasing_subsequence(a) <= n / MAX_DISPLACED_RATIO {
+        Choice::Merge
+    } else {
+        Choice::Quick
+    }
+}
+
+/// Sorts `a` with the algorithm best suited to how sorted it already is.
+///
+/// Small slices use insertion sort. Otherwise the presortedness of `a` is
+/// measured first: input with at most `n` inversions is finished by insertion
+/// sort in O(n), input with few long runs or with most elements already in
+/// increasing order is merged by `tim_sort`, and anything else goes to
+/// `quick_sort`. The measures take O(n log n) themselves, so this pays off
+/// for input expected to be partly sorted. Not stable.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::adaptive_sort;
+///
+/// let mut v: Vec<u32> = (0..1000).chain(0..1000).collect();
+/// adaptive_sort(&mut v);
+/// assert!(v.windows(2).all(|w| w[0] <= w[1]));
+/// ```
+pub fn adaptive_sort<T: PartialOrd + Copy>(a: &mut [T]) {
+    if a.len() <= INSERTION_SORT_THRESHOLD {
+        insertion_sort(a);
+        return;
+    }
+
+    match choose(a) {
+        Choice::Sorted => {}
+        Choice::Insertion => insertion_sort(a),
+        Choice::Merge => tim_sort(a),
+        Choice::Quick => quick_sort(a),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{patterns, XorShift};
+
+    #[test]
+    fn test_is_sorted() {
+        assert!(is_sorted::<u32>(&[]));
+        assert!(is_sorted(&[1]));
+        assert!(is_sorted(&[1, 1, 2]));
+        assert!(!is_sorted(&[2, 1]));
+        assert!(is_sorted_by(&[3, 2, 1], |a, b| b.cmp(a)));
+        assert!(!is_sorted_by(&[1, 2], |a, b| b.cmp(a)));
+    }
+
+    #[test]
+    fn test_presortedness() {
+        let mut rng = XorShift(0x50f7ed);
+
+        for &n in &[0, 1, 2, 50, 300] {
+            for v in patterns(&mut rng, n) {
+                let mut brute = 0;
+                for i in 0..n {
+                    for j in i + 1..n {
+                        if v[i] > v[j] {
+                            brute += 1;
+                        }
+                    }
+                }
+                assert_eq!(inversions(&v), brute);
+
+                let descents = v.windows(2).filter(|w| w[0] > w[1]).count();
+                assert_eq!(runs(&v), if n == 0 { 0 } else { descents + 1 });
+
+                // O(n^2) dynamic programming
+                let mut best = vec![1; n];
+                for i in 0..n {
+                    for j in 0..i {
+                        if v[j] <= v[i] {
+                            best[i] = best[i].max(best[j] + 1);
+                        }
+                    }
+                }
+                let brute = best.iter().cloned().max().unwrap_or(0);
+                assert_eq!(longest_increasing_subsequence(&v), brute);
+            }
+        }
+    }
+
+    #[test]
+    fn test_adaptive_sort() {
+        let mut rng = XorShift(0xada9);
+
+        for &n in &[0, 1, 10, 100, 5000] {
+            for mut v in patterns(&mut rng, n) {
+                let mut expected = v.clone();
+                expected.sort();
+                adaptive_sort(&mut v);
+                assert_eq!(v, expected);
+            }
+        }
+
+        // few long runs with too many inversions for insertion sort
+        let mut v: Vec<u32> = (0..3000).chain(0..3000).chain(0..3000).collect();
+        let mut expected = v.clone();
+        expected.sort();
+        adaptive_sort(&mut v);
+        assert_eq!(v, expected);
+    }
+
+    #[test]
+    fn test_adaptive_sort_choice() {
+        let sorted: Vec<u32> = (0..1000).collect();
+        assert_eq!(choose(&sorted), Choice::Sorted);
+
+        let mut swapped = sorted.clone();
+        for i in (0..1000).step_by(50) {
+            swapped.swap(i, i + 1);
+        }
+        assert_eq!(choose(&swapped), Choice::Insertion);
+
+        let two_runs: Vec<u32> = (0..500).chain(0..500).collect();
+        assert_eq!(choose(&two_runs), Choice::Merge);
+
+        // every 16th element out of place: many runs, long increasing
+        // subsequence
+        let mut rng = XorShift(0xd15);
+        let mut sprinkled = sorted.clone();
+        for i in (0..1000).step_by(16) {
+            sprinkled[i] = rng.below(1000) as u32;
+        }
+        assert!(runs(&sprinkled) > 1000 / MIN_AVERAGE_RUN);
+        assert_eq!(choose(&sprinkled), Choice::Merge);
+
+        let random: Vec<u32> = (0..1000).map(|_| rng.below(1000) as u32).collect();
+        assert_eq!(choose(&random), Choice::Quick);
+    }
+}
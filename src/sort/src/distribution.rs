@@ -0,0 +1,567 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Distribution sorts: radix, counting and bucket sort.
+
+use super::insertion_sort;
+
+/// A fixed-width key that can be sorted byte by byte.
+///
+/// The bytes must be order preserving: comparing two keys byte by byte,
+/// starting from level `LEVELS - 1` down to level `0`, has to give the same
+/// result as comparing the keys themselves.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::{radix_sort, RadixKey};
+///
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// struct Record {
+///     id: u32,
+///     weight: f32,
+/// }
+///
+/// impl RadixKey for Record {
+///     const LEVELS: usize = u32::LEVELS;
+///
+///     fn key_byte(&self, level: usize) -> u8 {
+///         self.id.key_byte(level)
+///     }
+/// }
+///
+/// let mut v = vec![
+///     Record { id: 3, weight: 0.5 },
+///     Record { id: 1, weight: 1.5 },
+///     Record { id: 2, weight: 2.5 },
+/// ];
+/// radix_sort(&mut v);
+/// assert_eq!(v.iter().map(|r| r.id).collect::<Vec<_>>(), vec![1, 2, 3]);
+/// ```
+pub trait RadixKey {
+    /// Number of bytes in the key.
+    const LEVELS: usize;
+
+    /// The byte at `level`, level `0` being the least significant.
+    fn key_byte(&self, level: usize) -> u8;
+}
+
+macro_rules! radix_key_unsigned {
+    ($($t:ty)*) => ($(
+        impl RadixKey for $t {
+            const LEVELS: usize = ::std::mem::size_of::<$t>();
+
+            #[inline]
+            fn key_byte(&self, level: usize) -> u8 {
+                (*self >> (level * 8)) as u8
+            }
+        }
+    )*)
+}
+
+radix_key_unsigned! { u8 u16 u32 u64 u128 usize }
+
+// flipping the sign bit maps two's complement onto unsigned order
+macro_rules! radix_key_signed {
+    ($($t:ty => $u:ty)*) => ($(
+        impl RadixKey for $t {
+            const LEVELS: usize = ::std::mem::size_of::<$t>();
+
+            #[inline]
+            fn key_byte(&self, level: usize) -> u8 {
+                let flipped = (*self as $u) ^ (1 << (<$u>::BITS - 1));
+                flipped.key_byte(level)
+            }
+        }
+    )*)
+}
+
+radix_key_signed! { i8 => u8 i16 => u16 i32 => u32 i64 => u64 i128 => u128 isize => usize }
+
+// negative floats get all bits flipped, positive ones only the sign bit, so
+// that the IEEE 754 bit patterns compare like the values they represent;
+// -0.0 sorts before 0.0, NaNs sort to the ends according to their sign
+macro_rules! radix_key_float {
+    ($($t:ty => $u:ty)*) => ($(
+        impl RadixKey for $t {
+            const LEVELS: usize = ::std::mem::size_of::<$t>();
+
+            #[inline]
+            fn key_byte(&self, level: usize) -> u8 {
+                let bits = self.to_bits();
+                let sign = 1 << (<$u>::BITS - 1);
+                let flipped = if bits & sign != 0 { !bits } else { bits ^ sign };
+                flipped.key_byte(level)
+            }
+        }
+    )*)
+}
+
+radix_key_float! { f32 => u32 f64 => u64 }
+
+/// Stable LSD radix sort.
+///
+/// Makes one counting pass per key byte, skipping bytes that are the same
+/// for every element, using O(n) extra space.
+pub fn radix_sort<T: RadixKey + Copy>(a: &mut [T]) {
+    radix_sort_by_key(a, |x| *x)
+}
+
+/// Stable LSD radix sort on the key extracted by `key`.
+pub fn radix_sort_by_key<T, K, F>(a: &mut [T], key: F)
+where
+    T: Copy,
+    K: RadixKey,
+    F: Fn(&T) -> K,
+{
+    let n = a.len();
+
+    if n <= 1 {
+        return;
+    }
+
+    // histograms for all levels in a single pass
+    let mut counts = vec![[0usize; 256]; K::LEVELS];
+    for x in a.iter() {
+        let k = key(x);
+        for (level, count) in counts.iter_mut().enumerate() {
+            count[k.key_byte(level) as usize] += 1;
+        }
+    }
+
+    let mut buf = a.to_vec();
+    let mut in_buf = false;
+
+    for (level, count) in counts.iter().enumerate() {
+        // every element has the same byte, the pass would not move anything
+        if count.contains(&n) {
+            continue;
+        }
+
+        let mut offsets = [0usize; 256];
+        let mut sum = 0;
+        for (offset, &c) in offsets.iter_mut().zip(count.iter()) {
+            *offset = sum;
+            sum += c;
+        }
+
+        if in_buf {
+            scatter(&buf, a, level, &mut offsets, &key);
+        } else {
+            scatter(a, &mut buf, level, &mut offsets, &key);
+        }
+        in_buf = !in_buf;
+    }
+
+    if in_buf {
+        a.copy_from_slice(&buf);
+    }
+}
+
+fn scatter<T, K, F>(src: &[T], dst: &mut [T], level: usize, offsets: &mut [usize; 256], key: &F)
+where
+    T: Copy,
+    K: RadixKey,
+    F: Fn(&T) -> K,
+{
+    for x in src {
+        let b = key(x).key_byte(level) as usize;
+        dst[offsets[b]] = *x;
+        offsets[b] += 1;
+    }
+}
+
+// buckets of byte strings at most this long are sorted with insertion sort
+const MSD_INSERTION_THRESHOLD: usize = 32;
+
+/// MSD radix sort (American flag sort) for byte strings.
+///
+/// Sorts in place in lexicographic byte order, a string sorting before all
+/// strings it is a proper prefix of. The sort is not stable.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::msd_radix_sort;
+///
+/// let mut v = vec!["she", "sells", "sea", "shells", "by", "the", "sea", "shore"];
+/// msd_radix_sort(&mut v);
+/// assert_eq!(v, vec!["by", "sea", "sea", "sells", "she", "shells", "shore", "the"]);
+/// ```
+pub fn msd_radix_sort<S: AsRef<[u8]>>(a: &mut [S]) {
+    if a.len() <= 1 {
+        return;
+    }
+
+    msd_sort(a, 0);
+}
+
+// bucket 0 holds the strings ending before `depth`, bucket `b + 1` byte `b`
+#[inline]
+fn bucket<S: AsRef<[u8]>>(s: &S, depth: usize) -> usize {
+    match s.as_ref().get(depth) {
+        None => 0,
+        Some(&b) => b as usize + 1,
+    }
+}
+
+// buckets still to sort are kept on an explicit stack rather than recursed
+// into, as keys with long common prefixes would need one frame per byte
+fn msd_sort<S: AsRef<[u8]>>(a: &mut [S], depth: usize) {
+    let mut work = vec![(0, a.len(), depth)];
+
+    while let Some((lo, hi, mut depth)) = work.pop() {
+        let a = &mut a[lo..hi];
+        let n = a.len();
+
+        if n <= MSD_INSERTION_THRESHOLD {
+            insertion_sort_suffix(a, depth);
+            continue;
+        }
+
+        let mut counts = [0usize; 257];
+        loop {
+            counts.iter_mut().for_each(|c| *c = 0);
+            for s in a.iter() {
+                counts[bucket(s, depth)] += 1;
+            }
+            if !counts[1..].contains(&n) {
+                break;
+            }
+            // common prefix, move on without splitting
+            depth += 1;
+        }
+
+        if counts[0] == n {
+            // all strings are equal
+            continue;
+        }
+
+        let mut starts = [0usize; 257];
+        let mut next = [0usize; 257];
+        let mut sum = 0;
+        for b in 0..257 {
+            starts[b] = sum;
+            next[b] = sum;
+            sum += counts[b];
+        }
+
+        // permute each element into its bucket
+        for b in 0..257 {
+            let end = starts[b] + counts[b];
+            while next[b] < end {
+                let k = bucket(&a[next[b]], depth);
+                if k == b {
+                    next[b] += 1;
+                } else {
+                    a.swap(next[b], next[k]);
+                    next[k] += 1;
+                }
+            }
+        }
+
+        for b in 1..257 {
+            if counts[b] > 1 {
+                let start = lo + starts[b];
+                work.push((start, start + counts[b], depth + 1));
+            }
+        }
+    }
+}
+
+// all strings share the first `depth` bytes
+fn insertion_sort_suffix<S: AsRef<[u8]>>(a: &mut [S], depth: usize) {
+    for i in 1..a.len() {
+        let mut j = i;
+        while j > 0 && a[j].as_ref()[depth..] < a[j - 1].as_ref()[depth..] {
+            a.swap(j, j - 1);
+            j -= 1;
+        }
+    }
+}
+
+// counting sort allocates a counter per possible key, so it only pays off
+// when the key range is comparable to the input size
+const COUNTING_SORT_MAX_RANGE: u64 = 1 << 16;
+
+/// Counting sort for keys in a small range.
+///
+/// Keys up to 8 bytes wide are mapped to `u64`; when `max - min` exceeds both
+/// `a.len()` and `2^16` the sort falls back to `radix_sort`.
+///
+/// # Panics
+///
+/// Panics if `T::LEVELS` is greater than 8.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::counting_sort;
+///
+/// let mut v = vec![-3i16, 7, 0, -3, 5, 1];
+/// counting_sort(&mut v);
+/// assert_eq!(v, vec![-3, -3, 0, 1, 5, 7]);
+/// ```
+pub fn counting_sort<T: RadixKey + Copy>(a: &mut [T]) {
+    assert!(T::LEVELS <= 8, "counting_sort supports keys up to 8 bytes");
+
+    let n = a.len();
+
+    if n <= 1 {
+        return;
+    }
+
+    let mut min = u64::MAX;
+    let mut max = 0;
+    for x in a.iter() {
+        let k = key_u64(x);
+        min = min.min(k);
+        max = max.max(k);
+    }
+
+    let range = max - min;
+    if range > COUNTING_SORT_MAX_RANGE && range > n as u64 {
+        radix_sort(a);
+        return;
+    }
+
+    counting_sort_by_key(a, range as usize + 1, |x| (key_u64(x) - min) as usize);
+}
+
+/// Stable counting sort on keys in `0..range` extracted by `key`.
+///
+/// # Panics
+///
+/// Panics if `key` returns a value not less than `range`.
+pub fn counting_sort_by_key<T, F>(a: &mut [T], range: usize, key: F)
+where
+    T: Copy,
+    F: Fn(&T) -> usize,
+{
+    let n = a.len();
+
+    if n <= 1 {
+        return;
+    }
+
+    let mut counts = vec![0usize; range];
+    for x in a.iter() {
+        counts[key(x)] += 1;
+    }
+
+    let mut sum = 0;
+    for c in counts.iter_mut() {
+        let tmp = *c;
+        *c = sum;
+        sum += tmp;
+    }
+
+    let buf = a.to_vec();
+    for x in buf.iter() {
+        let k = key(x);
+        a[counts[k]] = *x;
+        counts[k] += 1;
+    }
+}
+
+#[inline]
+fn key_u64<T: RadixKey>(x: &T) -> u64 {
+    (0..T::LEVELS).fold(0, |acc, level| {
+        acc | (x.key_byte(level) as u64) << (level * 8)
+    })
+}
+
+/// Bucket sort for uniformly distributed floating point values.
+///
+/// Spreads the values over `a.len()` equal-width buckets between the minimum
+/// and the maximum and insertion sorts each bucket, which takes O(n) expected
+/// time on uniform input. NaNs end up in unspecified positions.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::bucket_sort;
+///
+/// let mut v = vec![0.42, 0.32, 0.23, 0.52, 0.25, 0.47, 0.51];
+/// bucket_sort(&mut v);
+/// assert_eq!(v, vec![0.23, 0.25, 0.32, 0.42, 0.47, 0.51, 0.52]);
+/// ```
+pub fn bucket_sort<T: PartialOrd + Copy + Into<f64>>(a: &mut [T]) {
+    let n = a.len();
+
+    if n <= 1 {
+        return;
+    }
+
+    let mut min = f64::INFINITY;
+    let mut max = f64::NEG_INFINITY;
+    for &x in a.iter() {
+        let x = x.into();
+        min = min.min(x);
+        max = max.max(x);
+    }
+
+    let width = max - min;
+    if width <= 0.0 || !width.is_finite() {
+        // all values equal, or infinite range that cannot be bucketed
+        insertion_sort(a);
+        return;
+    }
+
+    let mut buckets: Vec<Vec<T>> = vec![Vec::new(); n];
+    for &x in a.iter() {
+        let i = ((x.into() - min) / width * (n - 1) as f64) as usize;
+        buckets[i.min(n - 1)].push(x);
+    }
+
+    let mut i = 0;
+    for mut bucket in buckets {
+        insertion_sort(&mut bucket);
+        a[i..i + bucket.len()].copy_from_slice(&bucket);
+        i += bucket.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{patterns, XorShift};
+
+    #[test]
+    fn test_radix_sort() {
+        let mut v = vec![4u32, 3, 5, 1, 2];
+        radix_sort(&mut v);
+        assert_eq!(v, vec![1, 2, 3, 4, 5]);
+
+        let mut rng = XorShift(0x1234_5678);
+        for &n in &[2, 100, 10_000] {
+            for v in patterns(&mut rng, n) {
+                let mut a = v.clone();
+                let mut expected = v.clone();
+                expected.sort();
+                radix_sort(&mut a);
+                assert_eq!(a, expected);
+
+                let mut a: Vec<u64> = v.iter().map(|&x| (x as u64) << 20 | x as u64).collect();
+                let mut expected = a.clone();
+                expected.sort();
+                radix_sort(&mut a);
+                assert_eq!(a, expected);
+
+                let mut a: Vec<i32> = v.iter().map(|&x| x as i32).collect();
+                let mut expected = a.clone();
+                expected.sort();
+                radix_sort(&mut a);
+                assert_eq!(a, expected);
+
+                let mut a: Vec<i64> = v.iter().map(|&x| x as i32 as i64 * 3).collect();
+                let mut expected = a.clone();
+                expected.sort();
+                radix_sort(&mut a);
+                assert_eq!(a, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_radix_sort_float() {
+        let mut v = vec![3.5f64, -0.0, -2.25, 0.0, 1e300, -1e-300, -1e300, 7.0];
+        radix_sort(&mut v);
+        assert_eq!(v, vec![-1e300, -2.25, -1e-300, -0.0, 0.0, 3.5, 7.0, 1e300]);
+        assert!(v[3].is_sign_negative());
+
+        let mut rng = XorShift(99);
+        let mut v: Vec<f32> = (0..1000)
+            .map(|_| rng.next() as i32 as f32 / 1000.0)
+            .collect();
+        let mut expected = v.clone();
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        radix_sort(&mut v);
+        assert_eq!(v, expected);
+    }
+
+    #[test]
+    fn test_radix_sort_by_key_stable() {
+        let v: Vec<(u16, usize)> = (0..1000).map(|i| ((i * 7919 % 13) as u16, i)).collect();
+        let mut a = v.clone();
+        radix_sort_by_key(&mut a, |x| x.0);
+        let mut expected = v.clone();
+        expected.sort_by_key(|x| x.0);
+        assert_eq!(a, expected);
+    }
+
+    #[test]
+    fn test_msd_radix_sort() {
+        let mut rng = XorShift(5);
+        let mut v: Vec<Vec<u8>> = (0..5000)
+            .map(|_| {
+                let len = rng.below(12) as usize;
+                (0..len).map(|_| b'a' + rng.below(3) as u8).collect()
+            })
+            .collect();
+        let mut expected = v.clone();
+        expected.sort();
+        msd_radix_sort(&mut v);
+        assert_eq!(v, expected);
+
+        let prefix = "x".repeat(1000);
+        let mut v: Vec<String> = (0..100).map(|i| format!("{}{}", prefix, 99 - i)).collect();
+        let mut expected = v.clone();
+        expected.sort();
+        msd_radix_sort(&mut v);
+        assert_eq!(v, expected);
+
+        // every length splits off one string, one level deeper each time
+        let mut v: Vec<String> = (0..4000).rev().map(|i| "a".repeat(i)).collect();
+        let mut expected = v.clone();
+        expected.sort();
+        msd_radix_sort(&mut v);
+        assert_eq!(v, expected);
+    }
+
+    #[test]
+    fn test_counting_sort() {
+        let mut v = vec![4u8, 3, 5, 1, 2, 3];
+        counting_sort(&mut v);
+        assert_eq!(v, vec![1, 2, 3, 3, 4, 5]);
+
+        let mut v = vec![-1i64, i64::MAX, i64::MIN, 0];
+        counting_sort(&mut v);
+        assert_eq!(v, vec![i64::MIN, -1, 0, i64::MAX]);
+
+        let mut v = vec![1u32, 4, 1, 0];
+        counting_sort_by_key(&mut v, 5, |&x| x as usize);
+        assert_eq!(v, vec![0, 1, 1, 4]);
+    }
+
+    #[test]
+    fn test_bucket_sort() {
+        let mut rng = XorShift(17);
+        let mut v: Vec<f64> = (0..10_000)
+            .map(|_| (rng.next() >> 11) as f64 / (1u64 << 53) as f64)
+            .collect();
+        let mut expected = v.clone();
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        bucket_sort(&mut v);
+        assert_eq!(v, expected);
+
+        let mut v = vec![2.0f32, 2.0, 2.0];
+        bucket_sort(&mut v);
+        assert_eq!(v, vec![2.0, 2.0, 2.0]);
+    }
+}
@@ -0,0 +1,592 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! External merge sort for inputs larger than memory.
+//!
+//! Records are read from a `Read` in chunks bounded by a memory limit, each
+//! chunk is sorted in memory and spilled to a temporary file as a sorted run,
+//! and the runs are k-way merged with a loser tree into a `Write`. Records
+//! are compared as byte strings.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate sort;
+//! use sort::external::{ExternalSorter, LineCodec};
+//!
+//! let input = "pear\napple\nfig\nbanana\n";
+//! let mut output = Vec::new();
+//! ExternalSorter::new(LineCodec)
+//!     .memory_limit(64)
+//!     .sort(input.as_bytes(), &mut output)
+//!     .unwrap();
+//! assert_eq!(output, b"apple\nbanana\nfig\npear\n");
+//! ```
+
+use super::msd_radix_sort;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Reads and writes records of a stream.
+pub trait Codec {
+    /// Reads the next record, or `None` at the end of the stream.
+    fn read<R: BufRead>(&self, r: &mut R) -> io::Result<Option<Vec<u8>>>;
+
+    /// Writes a record.
+    fn write<W: Write>(&self, w: &mut W, record: &[u8]) -> io::Result<()>;
+}
+
+/// Newline terminated text records.
+///
+/// The newline is not part of the record, every record is written with a
+/// trailing newline. A missing newline after the last record is accepted.
+#[derive(Debug, Clone, Copy)]
+pub struct LineCodec;
+
+impl Codec for LineCodec {
+    fn read<R: BufRead>(&self, r: &mut R) -> io::Result<Option<Vec<u8>>> {
+        let mut record = Vec::new();
+        if r.read_until(b'\n', &mut record)? == 0 {
+            return Ok(None);
+        }
+
+        if record.last() == Some(&b'\n') {
+            record.pop();
+        }
+        Ok(Some(record))
+    }
+
+    fn write<W: Write>(&self, w: &mut W, record: &[u8]) -> io::Result<()> {
+        w.write_all(record)?;
+        w.write_all(b"\n")
+    }
+}
+
+/// Fixed-width binary records.
+///
+/// Numeric keys should be stored big-endian so that byte order matches
+/// numeric order.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedWidthCodec {
+    width: usize,
+}
+
+impl FixedWidthCodec {
+    /// # Panics
+    ///
+    /// Panics if `width` is zero.
+    pub fn new(width: usize) -> FixedWidthCodec {
+        assert!(width > 0, "record width must be positive");
+        FixedWidthCodec { width }
+    }
+}
+
+impl Codec for FixedWidthCodec {
+    fn read<R: BufRead>(&self, r: &mut R) -> io::Result<Option<Vec<u8>>> {
+        let mut record = vec![0; self.width];
+        if read_exact_or_eof(r, &mut record)? {
+            Ok(Some(record))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn write<W: Write>(&self, w: &mut W, record: &[u8]) -> io::Result<()> {
+        debug_assert_eq!(record.len(), self.width);
+        w.write_all(record)
+    }
+}
+
+/// Records prefixed by their length as a little-endian `u32`.
+#[derive(Debug, Clone, Copy)]
+pub struct LengthPrefixedCodec;
+
+impl Codec for LengthPrefixedCodec {
+    fn read<R: BufRead>(&self, r: &mut R) -> io::Result<Option<Vec<u8>>> {
+        let mut len = [0u8; 4];
+        if !read_exact_or_eof(r, &mut len)? {
+            return Ok(None);
+        }
+
+        // grow the buffer as bytes arrive rather than trusting the prefix of
+        // a possibly corrupt file with a large allocation
+        let len = u32::from_le_bytes(len) as u64;
+        let mut record = Vec::new();
+        r.take(len).read_to_end(&mut record)?;
+        if (record.len() as u64) < len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "record shorter than its length prefix",
+            ));
+        }
+        Ok(Some(record))
+    }
+
+    fn write<W: Write>(&self, w: &mut W, record: &[u8]) -> io::Result<()> {
+        if record.len() > u32::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "record longer than u32::MAX bytes",
+            ));
+        }
+
+        w.write_all(&(record.len() as u32).to_le_bytes())?;
+        w.write_all(record)
+    }
+}
+
+/// Fills `buf`, returning `false` if the stream ended before the first byte
+/// and an `UnexpectedEof` error if it ended in the middle.
+fn read_exact_or_eof<R: Read>(r: &mut R, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+
+    while filled < buf.len() {
+        match r.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    if filled == 0 {
+        Ok(false)
+    } else if filled < buf.len() {
+        Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "truncated record",
+        ))
+    } else {
+        Ok(true)
+    }
+}
+
+const DEFAULT_MEMORY_LIMIT: usize = 64 << 20;
+const DEFAULT_FAN_IN: usize = 64;
+
+/// Sorts record streams that do not fit in memory.
+pub struct ExternalSorter<C: Codec> {
+    codec: C,
+    memory_limit: usize,
+    fan_in: usize,
+    temp_dir: PathBuf,
+}
+
+impl<C: Codec> ExternalSorter<C> {
+    /// Creates a sorter with a 64 MiB memory limit, merging up to 64 runs at
+    /// a time and spilling into `std::env::temp_dir()`.
+    pub fn new(codec: C) -> ExternalSorter<C> {
+        ExternalSorter {
+            codec,
+            memory_limit: DEFAULT_MEMORY_LIMIT,
+            fan_in: DEFAULT_FAN_IN,
+            temp_dir: env::temp_dir(),
+        }
+    }
+
+    /// Sets the approximate number of bytes of records held in memory.
+    pub fn memory_limit(mut self, bytes: usize) -> ExternalSorter<C> {
+        self.memory_limit = bytes;
+        self
+    }
+
+    /// Sets the maximum number of runs merged at once, at least 2. More runs
+    /// are merged in several passes.
+    pub fn fan_in(mut self, fan_in: usize) -> ExternalSorter<C> {
+        self.fan_in = fan_in.max(2);
+        self
+    }
+
+    /// Sets the directory for the temporary run files.
+    pub fn temp_dir<P: AsRef<Path>>(mut self, dir: P) -> ExternalSorter<C> {
+        self.temp_dir = dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Sorts all records of `input` into `output`.
+    pub fn sort<R: Read, W: Write>(&self, input: R, output: W) -> io::Result<()> {
+        let mut input = BufReader::new(input);
+        let mut output = BufWriter::new(output);
+
+        let mut runs = Vec::new();
+        let mut chunk = Vec::new();
+        let mut chunk_size = 0;
+
+        while let Some(record) = self.codec.read(&mut input)? {
+            chunk_size += record.len() + mem::size_of::<Vec<u8>>();
+            chunk.push(record);
+
+            if chunk_size >= self.memory_limit {
+                runs.push(self.spill(&mut chunk)?);
+                chunk_size = 0;
+            }
+        }
+
+        if runs.is_empty() {
+            // everything fit in memory
+            msd_radix_sort(&mut chunk);
+            for record in &chunk {
+                self.codec.write(&mut output, record)?;
+            }
+            return output.flush();
+        }
+
+        if !chunk.is_empty() {
+            runs.push(self.spill(&mut chunk)?);
+        }
+        drop(chunk);
+
+        while runs.len() > self.fan_in {
+            let mut merged = Vec::new();
+            let mut iter = runs.into_iter().peekable();
+            while iter.peek().is_some() {
+                let group: Vec<Run> = iter.by_ref().take(self.fan_in).collect();
+                let run = Run::create(&self.temp_dir)?;
+                {
+                    let mut w = BufWriter::new(File::create(&run.path)?);
+                    self.merge(&group, &mut w)?;
+                    w.flush()?;
+                }
+                merged.push(run);
+            }
+            runs = merged;
+        }
+
+        self.merge(&runs, &mut output)?;
+        output.flush()
+    }
+
+    /// Sorts `chunk` and writes it to a new run file, leaving `chunk` empty.
+    fn spill(&self, chunk: &mut Vec<Vec<u8>>) -> io::Result<Run> {
+        msd_radix_sort(chunk);
+
+        let run = Run::create(&self.temp_dir)?;
+        {
+            let mut w = BufWriter::new(File::create(&run.path)?);
+            for record in chunk.iter() {
+                self.codec.write(&mut w, record)?;
+            }
+            w.flush()?;
+        }
+
+        chunk.clear();
+        Ok(run)
+    }
+
+    fn merge<W: Write>(&self, runs: &[Run], w: &mut W) -> io::Result<()> {
+        let mut readers = Vec::with_capacity(runs.len());
+        let mut heads = Vec::with_capacity(runs.len());
+        for run in runs {
+            let mut r = BufReader::new(File::open(&run.path)?);
+            heads.push(self.codec.read(&mut r)?);
+            readers.push(r);
+        }
+
+        let mut tree = LoserTree::new(heads);
+        while let Some(i) = tree.winner() {
+            let next = self.codec.read(&mut readers[i])?;
+            let record = tree.replace(i, next);
+            self.codec.write(w, &record)?;
+        }
+
+        Ok(())
+    }
+}
+
+static RUN_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary run file, removed when dropped.
+struct Run {
+    path: PathBuf,
+}
+
+impl Run {
+    fn create(dir: &Path) -> io::Result<Run> {
+        loop {
+            let name = format!(
+                "sort-run-{}-{}.tmp",
+                process::id(),
+                RUN_COUNTER.fetch_add(1, Ordering::Relaxed)
+            );
+            let path = dir.join(name);
+
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Run { path }),
+                Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Tournament tree of losers over the heads of k sorted sources.
+///
+/// Leaves are the sources, every internal node keeps the loser of the match
+/// played there and the overall winner is kept separately, so replacing the
+/// winner's head takes a single leaf-to-root pass of log k comparisons.
+/// Exhausted sources (`None`) lose against everything; ties go to the lower
+/// source index.
+struct LoserTree {
+    heads: Vec<Option<Vec<u8>>>,
+    // tree[0] is the winner, tree[1..k] the losers of the internal nodes
+    tree: Vec<usize>,
+}
+
+impl LoserTree {
+    fn new(heads: Vec<Option<Vec<u8>>>) -> LoserTree {
+        let k = heads.len();
+        let mut t = LoserTree {
+            heads,
+            tree: vec![0; k.max(1)],
+        };
+
+        if k > 1 {
+            // winners of the subtrees, leaves at k..2k
+            let mut winners = vec![0; 2 * k];
+            for i in 0..k {
+                winners[k + i] = i;
+            }
+            for node in (1..k).rev() {
+                let (a, b) = (winners[2 * node], winners[2 * node + 1]);
+                if t.beats(a, b) {
+                    winners[node] = a;
+                    t.tree[node] = b;
+                } else {
+                    winners[node] = b;
+                    t.tree[node] = a;
+                }
+            }
+            t.tree[0] = winners[1];
+        }
+
+        t
+    }
+
+    fn beats(&self, a: usize, b: usize) -> bool {
+        match (&self.heads[a], &self.heads[b]) {
+            (Some(x), Some(y)) => x < y || (x == y && a < b),
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// Index of the source with the smallest head, `None` once all sources
+    /// are exhausted.
+    fn winner(&self) -> Option<usize> {
+        let w = self.tree[0];
+        if w < self.heads.len() && self.heads[w].is_some() {
+            Some(w)
+        } else {
+            None
+        }
+    }
+
+    /// Replaces the head of the winning source `i`, returning the old head.
+    fn replace(&mut self, i: usize, next: Option<Vec<u8>>) -> Vec<u8> {
+        debug_assert_eq!(Some(i), self.winner());
+
+        let record = mem::replace(&mut self.heads[i], next).unwrap();
+
+        let k = self.heads.len();
+        let mut winner = i;
+        let mut node = (k + i) / 2;
+        while node >= 1 {
+            if self.beats(self.tree[node], winner) {
+                mem::swap(&mut self.tree[node], &mut winner);
+            }
+            node /= 2;
+        }
+        self.tree[0] = winner;
+
+        record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::XorShift;
+
+    fn random_lines(rng: &mut XorShift, n: usize) -> Vec<Vec<u8>> {
+        (0..n)
+            .map(|_| {
+                let len = rng.below(10) as usize;
+                (0..len).map(|_| b'a' + rng.below(26) as u8).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_line_codec() {
+        let mut rng = XorShift(11);
+        let mut lines = random_lines(&mut rng, 2000);
+
+        let mut input = Vec::new();
+        for line in &lines {
+            input.extend_from_slice(line);
+            input.push(b'\n');
+        }
+
+        let mut output = Vec::new();
+        ExternalSorter::new(LineCodec)
+            .memory_limit(1024)
+            .fan_in(3)
+            .sort(&input[..], &mut output)
+            .unwrap();
+
+        lines.sort();
+        let mut expected = Vec::new();
+        for line in &lines {
+            expected.extend_from_slice(line);
+            expected.push(b'\n');
+        }
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_line_codec_in_memory() {
+        let mut output = Vec::new();
+        ExternalSorter::new(LineCodec)
+            .sort(&b"b\nc\na"[..], &mut output)
+            .unwrap();
+        assert_eq!(output, b"a\nb\nc\n");
+
+        let mut output = Vec::new();
+        ExternalSorter::new(LineCodec)
+            .sort(&b""[..], &mut output)
+            .unwrap();
+        assert_eq!(output, b"");
+    }
+
+    #[test]
+    fn test_fixed_width_codec() {
+        let mut rng = XorShift(12);
+        let mut values: Vec<u64> = (0..5000).map(|_| rng.next()).collect();
+
+        let mut input = Vec::new();
+        for v in &values {
+            input.extend_from_slice(&v.to_be_bytes());
+        }
+
+        let mut output = Vec::new();
+        ExternalSorter::new(FixedWidthCodec::new(8))
+            .memory_limit(4096)
+            .sort(&input[..], &mut output)
+            .unwrap();
+
+        values.sort();
+        let sorted: Vec<u64> = output
+            .chunks(8)
+            .map(|c| {
+                let mut b = [0u8; 8];
+                b.copy_from_slice(c);
+                u64::from_be_bytes(b)
+            })
+            .collect();
+        assert_eq!(sorted, values);
+
+        let err = ExternalSorter::new(FixedWidthCodec::new(8))
+            .sort(&[0u8; 12][..], &mut Vec::new())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_length_prefixed_codec() {
+        let mut rng = XorShift(13);
+        let mut records = random_lines(&mut rng, 3000);
+        records.push(b"with\nnewline".to_vec());
+
+        let mut input = Vec::new();
+        for r in &records {
+            LengthPrefixedCodec.write(&mut input, r).unwrap();
+        }
+
+        let mut output = Vec::new();
+        ExternalSorter::new(LengthPrefixedCodec)
+            .memory_limit(2048)
+            .fan_in(4)
+            .sort(&input[..], &mut output)
+            .unwrap();
+
+        records.sort();
+        let mut cursor = &output[..];
+        let mut sorted = Vec::new();
+        while let Some(r) = LengthPrefixedCodec.read(&mut cursor).unwrap() {
+            sorted.push(r);
+        }
+        assert_eq!(sorted, records);
+    }
+
+    #[test]
+    fn test_length_prefixed_codec_truncated() {
+        // a prefix claiming 4 GiB followed by three bytes
+        let input = [0xff, 0xff, 0xff, 0xff, 1, 2, 3];
+        let err = LengthPrefixedCodec.read(&mut &input[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let err = LengthPrefixedCodec.read(&mut &[1, 0][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_loser_tree() {
+        let mut sources: Vec<Vec<Vec<u8>>> = vec![
+            vec![b"a".to_vec(), b"d".to_vec(), b"g".to_vec()],
+            vec![],
+            vec![b"b".to_vec(), b"b".to_vec()],
+            vec![b"c".to_vec(), b"e".to_vec(), b"f".to_vec(), b"z".to_vec()],
+            vec![b"a".to_vec()],
+        ];
+        for s in sources.iter_mut() {
+            s.reverse();
+        }
+
+        let heads = sources.iter_mut().map(|s| s.pop()).collect();
+        let mut tree = LoserTree::new(heads);
+        let mut merged = Vec::new();
+        while let Some(i) = tree.winner() {
+            let next = sources[i].pop();
+            merged.push(tree.replace(i, next));
+        }
+
+        let merged: Vec<&[u8]> = merged.iter().map(|r| &r[..]).collect();
+        assert_eq!(
+            merged,
+            vec![
+                &b"a"[..],
+                b"a",
+                b"b",
+                b"b",
+                b"c",
+                b"d",
+                b"e",
+                b"f",
+                b"g",
+                b"z"
+            ]
+        );
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Gap sequences for `shell_sort_with`.
+//!
+//! reference:
+//!     https://oeis.org/A102549 (Ciura)
+//!     https://oeis.org/A108870 (Tokuda)
+
+/// The gaps used by shell sort on a slice of length `n`.
+pub trait GapSequence {
+    /// Returns the gaps in decreasing order, ending with 1. Empty if `n` is
+    /// at most 1.
+    fn gaps(&self, n: usize) -> Vec<usize>;
+}
+
+/// Knuth's `(3^k - 1) / 2`: 1, 4, 13, 40, ..., starting at the first gap
+/// not less than `n / 3`. This is what `shell_sort` uses.
+#[derive(Debug, Clone, Copy)]
+pub struct Knuth;
+
+impl GapSequence for Knuth {
+    fn gaps(&self, n: usize) -> Vec<usize> {
+        if n <= 1 {
+            return Vec::new();
+        }
+
+        let mut gaps = vec![1];
+        let mut h = 1;
+        while h < n / 3 {
+            h = h * 3 + 1;
+            gaps.push(h);
+        }
+
+        gaps.reverse();
+        gaps
+    }
+}
+
+/// Shell's original `n / 2^k`. O(n^2) in the worst case.
+#[derive(Debug, Clone, Copy)]
+pub struct Shell;
+
+impl GapSequence for Shell {
+    fn gaps(&self, n: usize) -> Vec<usize> {
+        let mut gaps = Vec::new();
+        let mut h = n / 2;
+        while h >= 1 {
+            gaps.push(h);
+            h /= 2;
+        }
+        gaps
+    }
+}
+
+/// Hibbard's `2^k - 1`: 1, 3, 7, 15, ... O(n^(3/2)).
+#[derive(Debug, Clone, Copy)]
+pub struct Hibbard;
+
+impl GapSequence for Hibbard {
+    fn gaps(&self, n: usize) -> Vec<usize> {
+        increasing_below(n, (1..).map(|k: u32| (1usize << k) - 1))
+    }
+}
+
+/// Sedgewick's 1986 sequence: 1, 5, 19, 41, 109, ... O(n^(4/3)).
+#[derive(Debug, Clone, Copy)]
+pub struct Sedgewick;
+
+impl GapSequence for Sedgewick {
+    fn gaps(&self, n: usize) -> Vec<usize> {
+        increasing_below(
+            n,
+            (0..).map(|k: u32| {
+                if k.is_multiple_of(2) {
+                    9 * ((1usize << k) - (1usize << (k / 2))) + 1
+                } else {
+                    8 * (1usize << k) - 6 * (1usize << k.div_ceil(2)) + 1
+                }
+            }),
+        )
+    }
+}
+
+/// Tokuda's `ceil((9 * (9/4)^k - 4) / 5)`: 1, 4, 9, 20, 46, 103, ...
+#[derive(Debug, Clone, Copy)]
+pub struct Tokuda;
+
+impl GapSequence for Tokuda {
+    fn gaps(&self, n: usize) -> Vec<usize> {
+        increasing_below(
+            n,
+            (0..).map(|k| ((9.0 * 2.25f64.powi(k) - 4.0) / 5.0).ceil() as usize),
+        )
+    }
+}
+
+/// Ciura's empirically found 1, 4, 10, 23, 57, 132, 301, 701, 1750,
+/// extended by `h * 2.25` past the end.
+#[derive(Debug, Clone, Copy)]
+pub struct Ciura;
+
+const CIURA: [usize; 9] = [1, 4, 10, 23, 57, 132, 301, 701, 1750];
+
+impl GapSequence for Ciura {
+    fn gaps(&self, n: usize) -> Vec<usize> {
+        let extended = (0..).scan(CIURA[CIURA.len() - 1], |h, _| {
+            *h = *h * 9 / 4;
+            Some(*h)
+        });
+        increasing_below(n, CIURA.iter().cloned().chain(extended))
+    }
+}
+
+/// Pratt's 3-smooth numbers `2^p * 3^q`: O(n log^2 n) but with many passes.
+#[derive(Debug, Clone, Copy)]
+pub struct Pratt;
+
+impl GapSequence for Pratt {
+    fn gaps(&self, n: usize) -> Vec<usize> {
+        let mut gaps = Vec::new();
+
+        let mut p = 1;
+        while p < n {
+            let mut h = p;
+            while h < n {
+                gaps.push(h);
+                h = match h.checked_mul(3) {
+                    Some(h) => h,
+                    None => break,
+                };
+            }
+            p = match p.checked_mul(2) {
+                Some(p) => p,
+                None => break,
+            };
+        }
+
+        gaps.sort_by(|a, b| b.cmp(a));
+        gaps
+    }
+}
+
+/// Collects the terms of an increasing sequence starting at 1 that are less
+/// than `n`, largest first.
+fn increasing_below<I: Iterator<Item = usize>>(n: usize, seq: I) -> Vec<usize> {
+    if n <= 1 {
+        return Vec::new();
+    }
+
+    let mut gaps: Vec<usize> = seq.take_while(|&h| h < n).collect();
+    gaps.reverse();
+    gaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gaps() {
+        assert_eq!(Knuth.gaps(1), vec![]);
+        assert_eq!(Knuth.gaps(10), vec![4, 1]);
+        assert_eq!(Knuth.gaps(100), vec![40, 13, 4, 1]);
+        assert_eq!(Shell.gaps(100), vec![50, 25, 12, 6, 3, 1]);
+        assert_eq!(Hibbard.gaps(100), vec![63, 31, 15, 7, 3, 1]);
+        assert_eq!(Sedgewick.gaps(200), vec![109, 41, 19, 5, 1]);
+        assert_eq!(Tokuda.gaps(500), vec![233, 103, 46, 20, 9, 4, 1]);
+        assert_eq!(Ciura.gaps(2), vec![1]);
+        assert_eq!(
+            Ciura.gaps(5000),
+            vec![3937, 1750, 701, 301, 132, 57, 23, 10, 4, 1]
+        );
+        assert_eq!(Pratt.gaps(13), vec![12, 9, 8, 6, 4, 3, 2, 1]);
+    }
+}
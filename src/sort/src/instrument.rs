@@ -0,0 +1,738 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Instrumented versions of the elementary sorts.
+//!
+//! The sorts here perform the same steps as their counterparts in the crate
+//! root, reporting every comparison, swap, write and recursion step to a
+//! `SortObserver`. `SortStats` counts the operations, `JsonTrace` and
+//! `AsciiFrames` record a step-by-step trace.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate sort;
+//! use sort::instrument::{self, SortStats};
+//!
+//! let mut v = vec![1, 2, 3, 5, 4];
+//! let mut stats = SortStats::default();
+//! instrument::bubble_sort(&mut v, &mut stats);
+//! assert_eq!(v, vec![1, 2, 3, 4, 5]);
+//! assert_eq!(stats.comparisons, 7);
+//! assert_eq!(stats.swaps, 1);
+//! ```
+
+use super::gaps::{GapSequence, Knuth};
+use super::network::{MAX_NETWORK_LEN, NETWORKS};
+use super::{log2, NINTHER_THRESHOLD};
+use std::fmt::Display;
+use std::io::{self, Write};
+
+/// Receives the operations performed by an instrumented sort.
+///
+/// Indices refer to the whole slice being sorted. Comparisons against a value
+/// held outside the slice (the key of an insertion, a partition pivot) report
+/// the index the value was read from. `swap` and `write` are called after the
+/// slice was modified.
+#[allow(unused_variables)]
+pub trait SortObserver<T> {
+    fn compare(&mut self, a: &[T], i: usize, j: usize) {}
+
+    fn swap(&mut self, a: &[T], i: usize, j: usize) {}
+
+    fn write(&mut self, a: &[T], i: usize) {}
+
+    /// Called when a recursive call at `depth` starts, the outermost call
+    /// being at depth 1.
+    fn enter(&mut self, depth: usize) {}
+
+    fn leave(&mut self, depth: usize) {}
+}
+
+/// Operation counters.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SortStats {
+    pub comparisons: usize,
+    pub swaps: usize,
+    pub writes: usize,
+    pub max_depth: usize,
+}
+
+impl<T> SortObserver<T> for SortStats {
+    fn compare(&mut self, _: &[T], _: usize, _: usize) {
+        self.comparisons += 1;
+    }
+
+    fn swap(&mut self, _: &[T], _: usize, _: usize) {
+        self.swaps += 1;
+    }
+
+    fn write(&mut self, _: &[T], _: usize) {
+        self.writes += 1;
+    }
+
+    fn enter(&mut self, depth: usize) {
+        self.max_depth = self.max_depth.max(depth);
+    }
+}
+
+/// Writes every operation as a JSON object on its own line.
+///
+/// Swaps and writes carry the state of the slice afterwards. Elements are
+/// written with `Display`, so they should be numbers to get valid JSON.
+///
+/// ```text
+/// {"step":0,"op":"compare","i":0,"j":1}
+/// {"step":1,"op":"swap","i":0,"j":1,"array":[1,2]}
+/// ```
+pub struct JsonTrace<W: Write> {
+    w: W,
+    step: usize,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> JsonTrace<W> {
+    pub fn new(w: W) -> JsonTrace<W> {
+        JsonTrace {
+            w,
+            step: 0,
+            error: None,
+        }
+    }
+
+    /// Returns the writer, or the first error hit while writing the trace.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => self.w.flush().map(|_| self.w),
+        }
+    }
+
+    fn emit<F: FnOnce(&mut W) -> io::Result<()>>(&mut self, op: &str, rest: F) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let step = self.step;
+        self.step += 1;
+        let w = &mut self.w;
+        let result = write!(w, "{{\"step\":{},\"op\":\"{}\"", step, op)
+            .and_then(|_| rest(w))
+            .and_then(|_| writeln!(w, "}}"));
+        if let Err(e) = result {
+            self.error = Some(e);
+        }
+    }
+}
+
+fn write_array<W: Write, T: Display>(w: &mut W, a: &[T]) -> io::Result<()> {
+    write!(w, ",\"array\":[")?;
+    for (k, x) in a.iter().enumerate() {
+        if k > 0 {
+            write!(w, ",")?;
+        }
+        write!(w, "{}", x)?;
+    }
+    write!(w, "]")
+}
+
+impl<T: Display, W: Write> SortObserver<T> for JsonTrace<W> {
+    fn compare(&mut self, _: &[T], i: usize, j: usize) {
+        self.emit("compare", |w| write!(w, ",\"i\":{},\"j\":{}", i, j));
+    }
+
+    fn swap(&mut self, a: &[T], i: usize, j: usize) {
+        self.emit("swap", |w| {
+            write!(w, ",\"i\":{},\"j\":{}", i, j)?;
+            write_array(w, a)
+        });
+    }
+
+    fn write(&mut self, a: &[T], i: usize) {
+        self.emit("write", |w| {
+            write!(w, ",\"i\":{}", i)?;
+            write_array(w, a)
+        });
+    }
+
+    fn enter(&mut self, depth: usize) {
+        self.emit("enter", |w| write!(w, ",\"depth\":{}", depth));
+    }
+
+    fn leave(&mut self, depth: usize) {
+        self.emit("leave", |w| write!(w, ",\"depth\":{}", depth));
+    }
+}
+
+/// Renders the slice as horizontal bars after every swap and write, one
+/// animation frame per modification. Frames are separated by an empty line
+/// and the modified rows are marked with `*`.
+///
+/// ```text
+/// frame 0: swap 0 1
+///   0 |####
+/// * 1 |########
+/// ```
+pub struct AsciiFrames<W: Write> {
+    w: W,
+    width: usize,
+    frame: usize,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> AsciiFrames<W> {
+    /// `width` is the length of the longest bar.
+    pub fn new(w: W, width: usize) -> AsciiFrames<W> {
+        AsciiFrames {
+            w,
+            width,
+            frame: 0,
+            error: None,
+        }
+    }
+
+    /// Returns the writer, or the first error hit while writing the frames.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => self.w.flush().map(|_| self.w),
+        }
+    }
+
+    fn render<T: Copy + Into<f64>>(&mut self, a: &[T], title: &str, marked: &[usize]) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let result = self.render_frame(a, title, marked);
+        self.frame += 1;
+        if let Err(e) = result {
+            self.error = Some(e);
+        }
+    }
+
+    fn render_frame<T: Copy + Into<f64>>(
+        &mut self,
+        a: &[T],
+        title: &str,
+        marked: &[usize],
+    ) -> io::Result<()> {
+        let (min, max) = a
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &x| {
+                let x = x.into();
+                (lo.min(x), hi.max(x))
+            });
+        let range = max - min;
+        let digits = a.len().saturating_sub(1).to_string().len();
+
+        if self.frame > 0 {
+            writeln!(self.w)?;
+        }
+        writeln!(self.w, "frame {}: {}", self.frame, title)?;
+
+        for (k, &x) in a.iter().enumerate() {
+            // the smallest value still gets a bar of length 1
+            let len = if range > 0.0 {
+                1 + ((x.into() - min) / range * (self.width.max(1) - 1) as f64).round() as usize
+            } else {
+                self.width.max(1)
+            };
+            let mark = if marked.contains(&k) { '*' } else { ' ' };
+            writeln!(
+                self.w,
+                "{} {:>digits$} |{}",
+                mark,
+                k,
+                "#".repeat(len),
+                digits = digits
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Copy + Into<f64>, W: Write> SortObserver<T> for AsciiFrames<W> {
+    fn swap(&mut self, a: &[T], i: usize, j: usize) {
+        self.render(a, &format!("swap {} {}", i, j), &[i, j]);
+    }
+
+    fn write(&mut self, a: &[T], i: usize) {
+        self.render(a, &format!("write {}", i), &[i]);
+    }
+}
+
+/// A slice whose accesses are reported to an observer.
+struct Tracked<'a, T: 'a, O: 'a> {
+    a: &'a mut [T],
+    obs: &'a mut O,
+    depth: usize,
+}
+
+impl<'a, T: PartialOrd + Copy, O: SortObserver<T>> Tracked<'a, T, O> {
+    fn new(a: &'a mut [T], obs: &'a mut O) -> Tracked<'a, T, O> {
+        Tracked { a, obs, depth: 0 }
+    }
+
+    #[inline]
+    fn lt(&mut self, i: usize, j: usize) -> bool {
+        self.obs.compare(self.a, i, j);
+        self.a[i] < self.a[j]
+    }
+
+    #[inline]
+    fn gt(&mut self, i: usize, j: usize) -> bool {
+        self.obs.compare(self.a, i, j);
+        self.a[i] > self.a[j]
+    }
+
+    // compares with `v`, which was read from `from`
+    #[inline]
+    fn lt_value(&mut self, i: usize, v: T, from: usize) -> bool {
+        self.obs.compare(self.a, i, from);
+        self.a[i] < v
+    }
+
+    #[inline]
+    fn gt_value(&mut self, i: usize, v: T, from: usize) -> bool {
+        self.obs.compare(self.a, i, from);
+        self.a[i] > v
+    }
+
+    #[inline]
+    fn swap(&mut self, i: usize, j: usize) {
+        self.a.swap(i, j);
+        self.obs.swap(self.a, i, j);
+    }
+
+    #[inline]
+    fn write(&mut self, i: usize, v: T) {
+        self.a[i] = v;
+        self.obs.write(self.a, i);
+    }
+
+    fn enter(&mut self) {
+        self.depth += 1;
+        self.obs.enter(self.depth);
+    }
+
+    fn leave(&mut self) {
+        self.obs.leave(self.depth);
+        self.depth -= 1;
+    }
+}
+
+pub fn bubble_sort<T, O>(a: &mut [T], obs: &mut O)
+where
+    T: PartialOrd + Copy,
+    O: SortObserver<T>,
+{
+    let n = a.len();
+
+    if n <= 1 {
+        return;
+    }
+
+    let mut t = Tracked::new(a, obs);
+    for i in 0..n {
+        let mut swapped = false;
+
+        for j in 0..(n - i - 1) {
+            if t.gt(j, j + 1) {
+                t.swap(j, j + 1);
+                swapped = true;
+            }
+        }
+
+        if !swapped {
+            break;
+        }
+    }
+}
+
+pub fn insertion_sort<T, O>(a: &mut [T], obs: &mut O)
+where
+    T: PartialOrd + Copy,
+    O: SortObserver<T>,
+{
+    let n = a.len();
+
+    if n <= 1 {
+        return;
+    }
+
+    let mut t = Tracked::new(a, obs);
+    insertion_sort_interval(&mut t, 0, n, 1);
+}
+
+fn insertion_sort_interval<T, O>(t: &mut Tracked<T, O>, lo: usize, hi: usize, interval: usize)
+where
+    T: PartialOrd + Copy,
+    O: SortObserver<T>,
+{
+    for i in (lo + interval)..hi {
+        let x = t.a[i];
+        let mut hole = i;
+        while hole >= lo + interval {
+            let j = hole - interval;
+            if t.gt_value(j, x, i) {
+                let y = t.a[j];
+                t.write(hole, y);
+                hole = j;
+            } else {
+                break;
+            }
+        }
+        t.write(hole, x);
+    }
+}
+
+pub fn shell_sort<T, O>(a: &mut [T], obs: &mut O)
+where
+    T: PartialOrd + Copy,
+    O: SortObserver<T>,
+{
+    shell_sort_with(a, Knuth, obs)
+}
+
+pub fn shell_sort_with<T, G, O>(a: &mut [T], seq: G, obs: &mut O)
+where
+    T: PartialOrd + Copy,
+    G: GapSequence,
+    O: SortObserver<T>,
+{
+    let n = a.len();
+
+    if n <= 1 {
+        return;
+    }
+
+    let mut t = Tracked::new(a, obs);
+    for interval in seq.gaps(n) {
+        insertion_sort_interval(&mut t, 0, n, interval);
+    }
+}
+
+pub fn selection_sort<T, O>(a: &mut [T], obs: &mut O)
+where
+    T: PartialOrd + Copy,
+    O: SortObserver<T>,
+{
+    let n = a.len();
+
+    if n <= 1 {
+        return;
+    }
+
+    let mut t = Tracked::new(a, obs);
+    for i in 0..n {
+        let mut min = i;
+        for j in i..n {
+            if t.lt(j, min) {
+                min = j;
+            }
+        }
+
+        if min != i {
+            t.swap(min, i);
+        }
+    }
+}
+
+pub fn quick_sort<T, O>(a: &mut [T], obs: &mut O)
+where
+    T: PartialOrd + Copy,
+    O: SortObserver<T>,
+{
+    let n = a.len();
+
+    if n <= 1 {
+        return;
+    }
+
+    let mut t = Tracked::new(a, obs);
+    quick_sort_part(&mut t, 0, n, 2 * log2(n));
+}
+
+fn quick_sort_part<T, O>(
+    t: &mut Tracked<T, O>,
+    mut lo: usize,
+    mut hi: usize,
+    mut depth_limit: usize,
+) where
+    T: PartialOrd + Copy,
+    O: SortObserver<T>,
+{
+    t.enter();
+
+    loop {
+        let n = hi - lo;
+
+        if n <= MAX_NETWORK_LEN {
+            sort_small(t, lo, hi);
+            break;
+        }
+
+        if depth_limit == 0 {
+            heap_sort(t, lo, hi);
+            break;
+        }
+        depth_limit -= 1;
+
+        let p = choose_pivot(t, lo, hi);
+        let (lt, gt) = partition3(t, lo, hi, p);
+
+        if lt - lo < hi - gt {
+            quick_sort_part(t, lo, lt, depth_limit);
+            lo = gt;
+        } else {
+            quick_sort_part(t, gt, hi, depth_limit);
+            hi = lt;
+        }
+    }
+
+    t.leave();
+}
+
+fn median_of_three<T, O>(t: &mut Tracked<T, O>, i: usize, j: usize, k: usize) -> usize
+where
+    T: PartialOrd + Copy,
+    O: SortObserver<T>,
+{
+    if t.lt(i, j) {
+        if t.lt(j, k) {
+            j
+        } else if t.lt(i, k) {
+            k
+        } else {
+            i
+        }
+    } else if t.lt(i, k) {
+        i
+    } else if t.lt(j, k) {
+        k
+    } else {
+        j
+    }
+}
+
+fn choose_pivot<T, O>(t: &mut Tracked<T, O>, lo: usize, hi: usize) -> usize
+where
+    T: PartialOrd + Copy,
+    O: SortObserver<T>,
+{
+    let n = hi - lo;
+    let mid = lo + n / 2;
+
+    if n < NINTHER_THRESHOLD {
+        median_of_three(t, lo, mid, hi - 1)
+    } else {
+        let step = n / 8;
+        let a = median_of_three(t, lo, lo + step, lo + 2 * step);
+        let b = median_of_three(t, mid - step, mid, mid + step);
+        let c = median_of_three(t, hi - 1 - 2 * step, hi - 1 - step, hi - 1);
+        median_of_three(t, a, b, c)
+    }
+}
+
+fn partition3<T, O>(t: &mut Tracked<T, O>, lo: usize, hi: usize, p: usize) -> (usize, usize)
+where
+    T: PartialOrd + Copy,
+    O: SortObserver<T>,
+{
+    let pivot = t.a[p];
+    let mut lt = lo;
+    let mut i = lo;
+    let mut gt = hi;
+
+    while i < gt {
+        if t.lt_value(i, pivot, p) {
+            t.swap(lt, i);
+            lt += 1;
+            i += 1;
+        } else if t.gt_value(i, pivot, p) {
+            gt -= 1;
+            t.swap(i, gt);
+        } else {
+            i += 1;
+        }
+    }
+
+    (lt, gt)
+}
+
+// the sorting network `quick_sort` finishes small slices with, reporting a
+// swap for each compare-exchange that reorders its pair
+fn sort_small<T, O>(t: &mut Tracked<T, O>, lo: usize, hi: usize)
+where
+    T: PartialOrd + Copy,
+    O: SortObserver<T>,
+{
+    for &(i, j) in NETWORKS[hi - lo] {
+        let (i, j) = (lo + i as usize, lo + j as usize);
+        if t.lt(j, i) {
+            t.swap(i, j);
+        }
+    }
+}
+
+fn heap_sort<T, O>(t: &mut Tracked<T, O>, lo: usize, hi: usize)
+where
+    T: PartialOrd + Copy,
+    O: SortObserver<T>,
+{
+    let n = hi - lo;
+
+    for i in (0..n / 2).rev() {
+        sift_down(t, lo, i, n);
+    }
+
+    for end in (1..n).rev() {
+        t.swap(lo, lo + end);
+        sift_down(t, lo, 0, end);
+    }
+}
+
+fn sift_down<T, O>(t: &mut Tracked<T, O>, lo: usize, mut root: usize, end: usize)
+where
+    T: PartialOrd + Copy,
+    O: SortObserver<T>,
+{
+    loop {
+        let mut child = 2 * root + 1;
+        if child >= end {
+            break;
+        }
+
+        if child + 1 < end && t.lt(lo + child, lo + child + 1) {
+            child += 1;
+        }
+
+        if t.lt(lo + root, lo + child) {
+            t.swap(lo + root, lo + child);
+            root = child;
+        } else {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{patterns, XorShift};
+
+    type Sort = fn(&mut [u32], &mut SortStats);
+
+    #[test]
+    fn test_sorts() {
+        let sorts: Vec<Sort> = vec![
+            bubble_sort,
+            insertion_sort,
+            shell_sort,
+            selection_sort,
+            quick_sort,
+        ];
+
+        let mut rng = XorShift(0x0b5e);
+        for &n in &[0, 1, 2, 50, 300] {
+            for v in patterns(&mut rng, n) {
+                let mut expected = v.clone();
+                expected.sort();
+
+                for sort in &sorts {
+                    let mut a = v.clone();
+                    sort(&mut a, &mut SortStats::default());
+                    assert_eq!(a, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sort_stats() {
+        let sorted: Vec<u32> = (0..5).collect();
+
+        let mut stats = SortStats::default();
+        bubble_sort(&mut sorted.clone(), &mut stats);
+        assert_eq!(
+            stats,
+            SortStats {
+                comparisons: 4,
+                swaps: 0,
+                writes: 0,
+                max_depth: 0,
+            }
+        );
+
+        let mut stats = SortStats::default();
+        insertion_sort(&mut sorted.clone(), &mut stats);
+        assert_eq!(stats.comparisons, 4);
+        assert_eq!(stats.writes, 4);
+
+        let mut stats = SortStats::default();
+        selection_sort(&mut [5u32, 4, 3, 2, 1], &mut stats);
+        assert_eq!(stats.comparisons, 15);
+        assert_eq!(stats.swaps, 2);
+
+        let mut stats = SortStats::default();
+        let mut v: Vec<u32> = (0..1000).rev().collect();
+        quick_sort(&mut v, &mut stats);
+        assert!(stats.max_depth >= 2);
+        assert!(stats.max_depth <= 2 * log2(1000) + 1);
+        // far from the quadratic behaviour of a last-element pivot
+        assert!(stats.comparisons < 4 * 1000 * log2(1000));
+
+        // small slices go through the same network as the uninstrumented sort
+        let mut stats = SortStats::default();
+        let mut v: Vec<u32> = (0..MAX_NETWORK_LEN as u32).rev().collect();
+        quick_sort(&mut v, &mut stats);
+        assert!(v.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(stats.comparisons, NETWORKS[MAX_NETWORK_LEN].len());
+        assert_eq!(stats.writes, 0);
+    }
+
+    #[test]
+    fn test_json_trace() {
+        let mut trace = JsonTrace::new(Vec::new());
+        bubble_sort(&mut [2, 1], &mut trace);
+        let out = String::from_utf8(trace.into_inner().unwrap()).unwrap();
+        assert_eq!(
+            out,
+            "{\"step\":0,\"op\":\"compare\",\"i\":0,\"j\":1}\n\
+             {\"step\":1,\"op\":\"swap\",\"i\":0,\"j\":1,\"array\":[1,2]}\n"
+        );
+
+        let mut trace = JsonTrace::new(Vec::new());
+        let mut stats = SortStats::default();
+        let mut v: Vec<u32> = (0..100).rev().collect();
+        quick_sort(&mut v.clone(), &mut trace);
+        quick_sort(&mut v, &mut stats);
+        let out = String::from_utf8(trace.into_inner().unwrap()).unwrap();
+        let enters = out.lines().filter(|l| l.contains("\"enter\"")).count();
+        assert_eq!(
+            out.lines().count(),
+            stats.comparisons + stats.swaps + stats.writes + 2 * enters
+        );
+    }
+
+    #[test]
+    fn test_ascii_frames() {
+        let mut frames = AsciiFrames::new(Vec::new(), 4);
+        bubble_sort(&mut [1u8, 3, 2], &mut frames);
+        let out = String::from_utf8(frames.into_inner().unwrap()).unwrap();
+        assert_eq!(out, "frame 0: swap 1 2\n  0 |#\n* 1 |###\n* 2 |####\n");
+    }
+}
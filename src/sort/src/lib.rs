@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use gaps::{GapSequence, Knuth};
+
 pub fn bubble_sort<T: PartialOrd + Copy>(a: &mut [T]) {
     let n = a.len();
 
@@ -44,7 +46,7 @@ fn insertion_sort_interval<T: PartialOrd + Copy>(a: &mut [T], interval: usize) {
         while j >= 0 {
             if a[j as usize] > x {
                 a[j as usize + interval] = a[j as usize];
-                j = j - interval as i32;
+                j -= interval as i32;
             } else {
                 break;
             }
@@ -64,20 +66,31 @@ pub fn insertion_sort<T: PartialOrd + Copy>(a: &mut [T]) {
 }
 
 pub fn shell_sort<T: PartialOrd + Copy>(a: &mut [T]) {
+    shell_sort_with(a, Knuth)
+}
+
+/// Shell sort with the gaps of `seq`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::gaps::Ciura;
+/// use sort::shell_sort_with;
+///
+/// let mut v = vec![4, 3, 5, 1, 2];
+/// shell_sort_with(&mut v, Ciura);
+/// assert_eq!(v, vec![1, 2, 3, 4, 5]);
+/// ```
+pub fn shell_sort_with<T: PartialOrd + Copy, G: GapSequence>(a: &mut [T], seq: G) {
     let n = a.len();
 
     if n <= 1 {
         return;
     }
 
-    let mut interval = 1usize;
-    while interval < n / 3 {
-        interval = interval * 3 + 1;
-    }
-
-    while interval >= 1 {
+    for interval in seq.gaps(n) {
         insertion_sort_interval(a, interval);
-        interval = interval / 3;
     }
 }
 
@@ -102,40 +115,225 @@ pub fn selection_sort<T: PartialOrd + Copy>(a: &mut [T]) {
     }
 }
 
+/// Introsort: quick sort with median-of-three (ninther for large slices) pivot
+/// selection and three-way partitioning, falling back to heap sort once the
+/// recursion depth exceeds `2 * log2(n)` and to insertion sort on small slices.
 pub fn quick_sort<T: PartialOrd + Copy>(a: &mut [T]) {
-    quick_sort_part(a)
+    let n = a.len();
+
+    if n <= 1 {
+        return;
+    }
+
+    quick_sort_part(a, 2 * log2(n))
+}
+
+const INSERTION_SORT_THRESHOLD: usize = 16;
+const NINTHER_THRESHOLD: usize = 128;
+
+fn quick_sort_part<T: PartialOrd + Copy>(mut a: &mut [T], mut depth_limit: usize) {
+    loop {
+        let n = a.len();
+
+        if n <= MAX_NETWORK_LEN {
+            network::sort_small(a);
+            return;
+        }
+
+        if depth_limit == 0 {
+            heap_sort(a);
+            return;
+        }
+        depth_limit -= 1;
+
+        let pivot = a[choose_pivot(a)];
+        let (lt, gt) = partition3(a, pivot);
+
+        // recurse into the smaller side and loop on the larger one,
+        // so the stack depth stays O(log n)
+        let tmp = a;
+        let (left, rest) = tmp.split_at_mut(lt);
+        let right = &mut rest[gt - lt..];
+        if left.len() < right.len() {
+            quick_sort_part(left, depth_limit);
+            a = right;
+        } else {
+            quick_sort_part(right, depth_limit);
+            a = left;
+        }
+    }
+}
+
+// `!lt(a, b)` reads as "not less than" without clippy mistaking it for `>=`,
+// which differs for partially ordered values
+#[inline]
+fn lt<T: PartialOrd>(a: &T, b: &T) -> bool {
+    a < b
+}
+
+#[inline]
+fn log2(n: usize) -> usize {
+    debug_assert!(n > 0);
+    (usize::BITS - 1 - n.leading_zeros()) as usize
+}
+
+fn median_of_three<T: PartialOrd>(a: &[T], i: usize, j: usize, k: usize) -> usize {
+    if a[i] < a[j] {
+        if a[j] < a[k] {
+            j
+        } else if a[i] < a[k] {
+            k
+        } else {
+            i
+        }
+    } else if a[i] < a[k] {
+        i
+    } else if a[j] < a[k] {
+        k
+    } else {
+        j
+    }
 }
 
-fn quick_sort_part<T: PartialOrd + Copy>(a: &mut [T]) {
+fn choose_pivot<T: PartialOrd>(a: &[T]) -> usize {
+    let n = a.len();
+    let mid = n / 2;
+
+    if n < NINTHER_THRESHOLD {
+        median_of_three(a, 0, mid, n - 1)
+    } else {
+        // Tukey's ninther
+        let step = n / 8;
+        let lo = median_of_three(a, 0, step, 2 * step);
+        let mi = median_of_three(a, mid - step, mid, mid + step);
+        let hi = median_of_three(a, n - 1 - 2 * step, n - 1 - step, n - 1);
+        median_of_three(a, lo, mi, hi)
+    }
+}
+
+/// Dutch national flag partition.
+///
+/// Returns `(lt, gt)` such that `a[..lt] < pivot`, `a[lt..gt] == pivot`
+/// and `a[gt..] > pivot`.
+fn partition3<T: PartialOrd + Copy>(a: &mut [T], pivot: T) -> (usize, usize) {
+    let mut lt = 0;
+    let mut i = 0;
+    let mut gt = a.len();
+
+    while i < gt {
+        if a[i] < pivot {
+            a.swap(lt, i);
+            lt += 1;
+            i += 1;
+        } else if a[i] > pivot {
+            gt -= 1;
+            a.swap(i, gt);
+        } else {
+            i += 1;
+        }
+    }
+
+    (lt, gt)
+}
+
+/// Stable top-down merge sort using O(n) extra space.
+pub fn merge_sort<T: PartialOrd + Copy>(a: &mut [T]) {
     let n = a.len();
 
     if n <= 1 {
         return;
     }
 
-    let p = partition(a);
-    quick_sort_part(&mut a[0..p]);
-    quick_sort_part(&mut a[p + 1..n]);
+    let mut buf = a.to_vec();
+    merge_sort_part(a, &mut buf);
 }
 
-fn partition<T: PartialOrd + Copy>(a: &mut [T]) -> usize {
+fn merge_sort_part<T: PartialOrd + Copy>(a: &mut [T], buf: &mut [T]) {
     let n = a.len();
-    let pivot = a[n - 1];
+    debug_assert_eq!(n, buf.len());
+
+    // sorting networks would be faster here but reorder equal elements
+    if n <= INSERTION_SORT_THRESHOLD {
+        insertion_sort(a);
+        return;
+    }
+
+    let mid = n / 2;
+    {
+        let (a_left, a_right) = a.split_at_mut(mid);
+        let (buf_left, buf_right) = buf.split_at_mut(mid);
+        merge_sort_part(a_left, buf_left);
+        merge_sort_part(a_right, buf_right);
+    }
+
+    // the halves are already in order
+    if !lt(&a[mid], &a[mid - 1]) {
+        return;
+    }
+
+    buf.copy_from_slice(a);
+    let (left, right) = buf.split_at(mid);
+    merge(left, right, a);
+}
+
+/// Merges the sorted slices `left` and `right` into `out`, taking from
+/// `left` first on ties.
+fn merge<T: PartialOrd + Copy>(left: &[T], right: &[T], out: &mut [T]) {
+    debug_assert_eq!(left.len() + right.len(), out.len());
 
     let mut i = 0;
+    let mut j = 0;
 
-    for j in 0..(n - 1) {
-        if a[j] < pivot {
-            a.swap(i, j);
+    for x in out.iter_mut() {
+        if j == right.len() || (i < left.len() && !lt(&right[j], &left[i])) {
+            *x = left[i];
             i += 1;
+        } else {
+            *x = right[j];
+            j += 1;
         }
     }
+}
 
-    a.swap(i, n - 1);
+pub fn heap_sort<T: PartialOrd + Copy>(a: &mut [T]) {
+    let n = a.len();
 
-    return i;
+    if n <= 1 {
+        return;
+    }
+
+    for i in (0..n / 2).rev() {
+        sift_down(a, i, n);
+    }
+
+    for end in (1..n).rev() {
+        a.swap(0, end);
+        sift_down(a, 0, end);
+    }
 }
 
+fn sift_down<T: PartialOrd + Copy>(a: &mut [T], mut root: usize, end: usize) {
+    loop {
+        let mut child = 2 * root + 1;
+        if child >= end {
+            break;
+        }
+
+        if child + 1 < end && a[child] < a[child + 1] {
+            child += 1;
+        }
+
+        if a[root] < a[child] {
+            a.swap(root, child);
+            root = child;
+        } else {
+            break;
+        }
+    }
+}
+
+/// Returns the `n`-th smallest element (1-based), reordering `a` as
+/// `select_nth_unstable` does.
 pub fn smallest_n<T: PartialOrd + Copy>(a: &mut [T], n: usize) -> Option<T> {
     let len = a.len();
 
@@ -143,19 +341,42 @@ pub fn smallest_n<T: PartialOrd + Copy>(a: &mut [T], n: usize) -> Option<T> {
         return None;
     }
 
-    let p = partition(a);
-    if p == n - 1 {
-        Some(a[p])
-    } else if p < n - 1 {
-        smallest_n(&mut a[p + 1..len], n - p - 1)
-    } else {
-        smallest_n(&mut a[0..p], n)
-    }
+    let (_, nth, _) = select_nth_unstable(a, n - 1);
+    Some(*nth)
 }
 
+pub mod external;
+pub mod gaps;
+pub mod instrument;
+
+mod adaptive;
+mod distribution;
+mod network;
+mod parallel;
+mod pdqsort;
+mod select;
+mod timsort;
+
+pub use adaptive::{
+    adaptive_sort, inversions, is_sorted, is_sorted_by, longest_increasing_subsequence, runs,
+};
+pub use distribution::{
+    bucket_sort, counting_sort, counting_sort_by_key, msd_radix_sort, radix_sort,
+    radix_sort_by_key, RadixKey,
+};
+pub use network::{bitonic_sort, odd_even_merge_sort, sort_network, MAX_NETWORK_LEN};
+pub use parallel::{par_merge_sort, par_quick_sort, ParSort};
+pub use pdqsort::pdq_sort;
+pub use select::{median, partial_sort, quantiles, select_nth_unstable, top_k, top_k_by};
+pub use timsort::tim_sort;
+
+#[cfg(test)]
+mod testing;
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use testing::{patterns, XorShift};
 
     #[test]
     fn test_bubble_sort() {
@@ -190,6 +411,35 @@ mod tests {
         assert_eq!(v, vec![1, 2, 3, 4, 5]);
     }
 
+    #[test]
+    fn test_shell_sort_with() {
+        let mut rng = XorShift(0x6a95);
+
+        for v in patterns(&mut rng, 3000) {
+            let mut expected = v.clone();
+            expected.sort();
+
+            let mut a = v.clone();
+            shell_sort_with(&mut a, gaps::Shell);
+            assert_eq!(a, expected);
+            let mut a = v.clone();
+            shell_sort_with(&mut a, gaps::Hibbard);
+            assert_eq!(a, expected);
+            let mut a = v.clone();
+            shell_sort_with(&mut a, gaps::Sedgewick);
+            assert_eq!(a, expected);
+            let mut a = v.clone();
+            shell_sort_with(&mut a, gaps::Tokuda);
+            assert_eq!(a, expected);
+            let mut a = v.clone();
+            shell_sort_with(&mut a, gaps::Ciura);
+            assert_eq!(a, expected);
+            let mut a = v.clone();
+            shell_sort_with(&mut a, gaps::Pratt);
+            assert_eq!(a, expected);
+        }
+    }
+
     #[test]
     fn test_selection_sort() {
         let mut v = vec![4, 3, 5, 1, 2];
@@ -212,6 +462,70 @@ mod tests {
         assert_eq!(v, vec![1, 2, 3, 4, 5]);
     }
 
+    #[test]
+    fn test_quick_sort_large() {
+        let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+
+        let mut v: Vec<u32> = (0..10_000).map(|_| rng.next() as u32).collect();
+        let mut expected = v.clone();
+        expected.sort();
+        quick_sort(&mut v);
+        assert_eq!(v, expected);
+
+        // few distinct values
+        let mut v: Vec<u32> = (0..10_000).map(|_| rng.next() as u32 % 4).collect();
+        let mut expected = v.clone();
+        expected.sort();
+        quick_sort(&mut v);
+        assert_eq!(v, expected);
+
+        let mut v: Vec<u32> = (0..100_000).collect();
+        quick_sort(&mut v);
+        assert!(v.windows(2).all(|w| w[0] <= w[1]));
+
+        let mut v: Vec<u32> = (0..100_000).rev().collect();
+        quick_sort(&mut v);
+        assert!(v.windows(2).all(|w| w[0] <= w[1]));
+
+        let mut v = vec![7u32; 100_000];
+        quick_sort(&mut v);
+        assert_eq!(v, vec![7u32; 100_000]);
+    }
+
+    #[test]
+    fn test_merge_sort() {
+        let mut v = vec![4, 3, 5, 1, 2];
+        merge_sort(&mut v);
+        assert_eq!(v, vec![1, 2, 3, 4, 5]);
+
+        let mut v = vec![5, 4, 3, 2, 1];
+        merge_sort(&mut v);
+        assert_eq!(v, vec![1, 2, 3, 4, 5]);
+
+        let mut rng = XorShift(0x5151);
+        for mut v in patterns(&mut rng, 5000) {
+            let mut expected = v.clone();
+            expected.sort();
+            merge_sort(&mut v);
+            assert_eq!(v, expected);
+        }
+    }
+
+    #[test]
+    fn test_heap_sort() {
+        let mut v = vec![4, 3, 5, 1, 2];
+        heap_sort(&mut v);
+        assert_eq!(v, vec![1, 2, 3, 4, 5]);
+
+        let mut v = vec![5, 4, 3, 2, 1];
+        heap_sort(&mut v);
+        assert_eq!(v, vec![1, 2, 3, 4, 5]);
+
+        let mut v = vec![2, 1, 2, 1, 2];
+        heap_sort(&mut v);
+        assert_eq!(v, vec![1, 1, 2, 2, 2]);
+    }
+
     #[test]
     fn test_smallest_n() {
         let mut v = vec![4, 3, 5, 1, 2];
@@ -0,0 +1,395 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sorting networks: fixed sequences of compare-exchanges whose order does
+//! not depend on the data, so small slices sort without unpredictable
+//! branches.
+//!
+//! reference:
+//!     https://bertdobbelaere.github.io/sorting_networks.html
+//!     https://en.wikipedia.org/wiki/Batcher_odd%E2%80%93even_mergesort
+
+use super::lt;
+
+/// Longest input covered by `sort_network`.
+pub const MAX_NETWORK_LEN: usize = 16;
+
+// the smallest known networks for each length, one layer of independent
+// comparators per line; 14 and 15 are Green's 16-input network with the
+// highest wires removed
+#[rustfmt::skip]
+pub(crate) const NETWORKS: [&[(u8, u8)]; MAX_NETWORK_LEN + 1] = [
+    // 0, 1
+    &[],
+    &[],
+    // 2: 1 comparator, depth 1
+    &[
+        (0, 1),
+    ],
+    // 3: 3 comparators, depth 3
+    &[
+        (0, 2),
+        (0, 1),
+        (1, 2),
+    ],
+    // 4: 5 comparators, depth 3
+    &[
+        (0, 2), (1, 3),
+        (0, 1), (2, 3),
+        (1, 2),
+    ],
+    // 5: 9 comparators, depth 5
+    &[
+        (0, 3), (1, 4),
+        (0, 2), (1, 3),
+        (0, 1), (2, 4),
+        (1, 2), (3, 4),
+        (2, 3),
+    ],
+    // 6: 12 comparators, depth 5
+    &[
+        (0, 5), (1, 3), (2, 4),
+        (1, 2), (3, 4),
+        (0, 3), (2, 5),
+        (0, 1), (2, 3), (4, 5),
+        (1, 2), (3, 4),
+    ],
+    // 7: 16 comparators, depth 6
+    &[
+        (0, 6), (2, 3), (4, 5),
+        (0, 2), (1, 4), (3, 6),
+        (0, 1), (2, 5), (3, 4),
+        (1, 2), (4, 6),
+        (2, 3), (4, 5),
+        (1, 2), (3, 4), (5, 6),
+    ],
+    // 8: 19 comparators, depth 6
+    &[
+        (0, 2), (1, 3), (4, 6), (5, 7),
+        (0, 4), (1, 5), (2, 6), (3, 7),
+        (0, 1), (2, 3), (4, 5), (6, 7),
+        (2, 4), (3, 5),
+        (1, 4), (3, 6),
+        (1, 2), (3, 4), (5, 6),
+    ],
+    // 9: 25 comparators, depth 7
+    &[
+        (0, 3), (1, 7), (2, 5), (4, 8),
+        (0, 7), (2, 4), (3, 8), (5, 6),
+        (0, 2), (1, 3), (4, 5), (7, 8),
+        (1, 4), (3, 6), (5, 7),
+        (0, 1), (2, 4), (3, 5), (6, 8),
+        (2, 3), (4, 5), (6, 7),
+        (1, 2), (3, 4), (5, 6),
+    ],
+    // 10: 29 comparators, depth 8
+    &[
+        (0, 8), (1, 9), (2, 7), (3, 5), (4, 6),
+        (0, 2), (1, 4), (5, 8), (7, 9),
+        (0, 3), (2, 4), (5, 7), (6, 9),
+        (0, 1), (3, 6), (8, 9),
+        (1, 5), (2, 3), (4, 8), (6, 7),
+        (1, 2), (3, 5), (4, 6), (7, 8),
+        (2, 3), (4, 5), (6, 7),
+        (3, 4), (5, 6),
+    ],
+    // 11: 35 comparators, depth 8
+    &[
+        (0, 9), (1, 6), (2, 4), (3, 7), (5, 8),
+        (0, 1), (3, 5), (4, 10), (6, 9), (7, 8),
+        (1, 3), (2, 5), (4, 7), (8, 10),
+        (0, 4), (1, 2), (3, 7), (5, 9), (6, 8),
+        (0, 1), (2, 6), (4, 5), (7, 8), (9, 10),
+        (2, 4), (3, 6), (5, 7), (8, 9),
+        (1, 2), (3, 4), (5, 6), (7, 8),
+        (2, 3), (4, 5), (6, 7),
+    ],
+    // 12: 39 comparators, depth 9
+    &[
+        (0, 8), (1, 7), (2, 6), (3, 11), (4, 10), (5, 9),
+        (0, 1), (2, 5), (3, 4), (6, 9), (7, 8), (10, 11),
+        (0, 2), (1, 6), (5, 10), (9, 11),
+        (0, 3), (1, 2), (4, 6), (5, 7), (8, 11), (9, 10),
+        (1, 4), (3, 5), (6, 8), (7, 10),
+        (1, 3), (2, 5), (6, 9), (8, 10),
+        (2, 3), (4, 5), (6, 7), (8, 9),
+        (4, 6), (5, 7),
+        (3, 4), (5, 6), (7, 8),
+    ],
+    // 13: 45 comparators, depth 10
+    &[
+        (0, 12), (1, 10), (2, 9), (3, 7), (5, 11), (6, 8),
+        (1, 6), (2, 3), (4, 11), (7, 9), (8, 10),
+        (0, 4), (1, 2), (3, 6), (7, 8), (9, 10), (11, 12),
+        (4, 6), (5, 9), (8, 11), (10, 12),
+        (0, 5), (3, 8), (4, 7), (6, 11), (9, 10),
+        (0, 1), (2, 5), (6, 9), (7, 8), (10, 11),
+        (1, 3), (2, 4), (5, 6), (9, 10),
+        (1, 2), (3, 4), (5, 7), (6, 8),
+        (2, 3), (4, 5), (6, 7), (8, 9),
+        (3, 4), (5, 6),
+    ],
+    // 14: 51 comparators, depth 10
+    &[
+        (0, 1), (2, 3), (4, 5), (6, 7), (8, 9), (10, 11), (12, 13),
+        (0, 2), (1, 3), (4, 6), (5, 7), (8, 10), (9, 11),
+        (0, 4), (1, 5), (2, 6), (3, 7), (8, 12), (9, 13),
+        (0, 8), (1, 9), (2, 10), (3, 11), (4, 12), (5, 13),
+        (5, 10), (6, 9), (3, 12), (7, 11), (1, 2), (4, 8),
+        (1, 4), (7, 13), (2, 8), (5, 6), (9, 10),
+        (2, 4), (11, 13), (3, 8), (7, 12),
+        (6, 8), (10, 12), (3, 5), (7, 9),
+        (3, 4), (5, 6), (7, 8), (9, 10), (11, 12),
+        (6, 7), (8, 9),
+    ],
+    // 15: 56 comparators, depth 10
+    &[
+        (0, 1), (2, 3), (4, 5), (6, 7), (8, 9), (10, 11), (12, 13),
+        (0, 2), (1, 3), (4, 6), (5, 7), (8, 10), (9, 11), (12, 14),
+        (0, 4), (1, 5), (2, 6), (3, 7), (8, 12), (9, 13), (10, 14),
+        (0, 8), (1, 9), (2, 10), (3, 11), (4, 12), (5, 13), (6, 14),
+        (5, 10), (6, 9), (3, 12), (13, 14), (7, 11), (1, 2), (4, 8),
+        (1, 4), (7, 13), (2, 8), (11, 14), (5, 6), (9, 10),
+        (2, 4), (11, 13), (3, 8), (7, 12),
+        (6, 8), (10, 12), (3, 5), (7, 9),
+        (3, 4), (5, 6), (7, 8), (9, 10), (11, 12),
+        (6, 7), (8, 9),
+    ],
+    // 16: 60 comparators, depth 10
+    &[
+        (0, 1), (2, 3), (4, 5), (6, 7), (8, 9), (10, 11), (12, 13), (14, 15),
+        (0, 2), (1, 3), (4, 6), (5, 7), (8, 10), (9, 11), (12, 14), (13, 15),
+        (0, 4), (1, 5), (2, 6), (3, 7), (8, 12), (9, 13), (10, 14), (11, 15),
+        (0, 8), (1, 9), (2, 10), (3, 11), (4, 12), (5, 13), (6, 14), (7, 15),
+        (5, 10), (6, 9), (3, 12), (13, 14), (7, 11), (1, 2), (4, 8),
+        (1, 4), (7, 13), (2, 8), (11, 14), (5, 6), (9, 10),
+        (2, 4), (11, 13), (3, 8), (7, 12),
+        (6, 8), (10, 12), (3, 5), (7, 9),
+        (3, 4), (5, 6), (7, 8), (9, 10), (11, 12),
+        (6, 7), (8, 9),
+    ],
+];
+
+/// Sorts an array of up to 16 elements with a size-optimal sorting network.
+/// Not stable.
+///
+/// Fails to compile for `N > 16`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::sort_network;
+///
+/// let mut a = [5, 2, 8, 1, 9, 3];
+/// sort_network(&mut a);
+/// assert_eq!(a, [1, 2, 3, 5, 8, 9]);
+/// ```
+#[inline]
+pub fn sort_network<T: PartialOrd + Copy, const N: usize>(a: &mut [T; N]) {
+    const { assert!(N <= MAX_NETWORK_LEN, "no sorting network for this length") };
+
+    for &(i, j) in NETWORKS[N] {
+        compare_exchange(a, i as usize, j as usize);
+    }
+}
+
+/// Sorts a slice of at most `MAX_NETWORK_LEN` elements with the network for
+/// its length.
+pub(crate) fn sort_small<T: PartialOrd + Copy>(a: &mut [T]) {
+    debug_assert!(a.len() <= MAX_NETWORK_LEN);
+
+    for &(i, j) in NETWORKS[a.len()] {
+        compare_exchange(a, i as usize, j as usize);
+    }
+}
+
+/// Bitonic sort, O(n log^2 n) comparisons in a fixed pattern. Not stable.
+///
+/// # Panics
+///
+/// Panics if the length of `a` is not a power of two.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::bitonic_sort;
+///
+/// let mut v = vec![7, 3, 6, 1, 8, 2, 5, 4];
+/// bitonic_sort(&mut v);
+/// assert_eq!(v, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+/// ```
+pub fn bitonic_sort<T: PartialOrd + Copy>(a: &mut [T]) {
+    let n = check_power_of_two(a);
+
+    let mut k = 2;
+    while k <= n {
+        let mut j = k / 2;
+        while j > 0 {
+            for i in 0..n {
+                let l = i ^ j;
+                if l > i {
+                    // blocks of `k` alternate between ascending and descending
+                    if i & k == 0 {
+                        compare_exchange(a, i, l);
+                    } else {
+                        compare_exchange(a, l, i);
+                    }
+                }
+            }
+            j /= 2;
+        }
+        k *= 2;
+    }
+}
+
+/// Batcher's odd-even merge sort, slightly fewer comparisons than
+/// `bitonic_sort` in the same O(n log^2 n) fixed pattern. Not stable.
+///
+/// # Panics
+///
+/// Panics if the length of `a` is not a power of two.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::odd_even_merge_sort;
+///
+/// let mut v = vec![7, 3, 6, 1, 8, 2, 5, 4];
+/// odd_even_merge_sort(&mut v);
+/// assert_eq!(v, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+/// ```
+pub fn odd_even_merge_sort<T: PartialOrd + Copy>(a: &mut [T]) {
+    let n = check_power_of_two(a);
+
+    let mut p = 1;
+    while p < n {
+        let mut k = p;
+        while k > 0 {
+            let mut j = k % p;
+            while j + k < n {
+                for i in 0..k.min(n - j - k) {
+                    // only compare within the pair of sorted blocks being merged
+                    if (i + j) / (2 * p) == (i + j + k) / (2 * p) {
+                        compare_exchange(a, i + j, i + j + k);
+                    }
+                }
+                j += 2 * k;
+            }
+            k /= 2;
+        }
+        p *= 2;
+    }
+}
+
+fn check_power_of_two<T>(a: &[T]) -> usize {
+    let n = a.len();
+    assert!(
+        n <= 1 || n.is_power_of_two(),
+        "length {} is not a power of two",
+        n
+    );
+    n
+}
+
+/// Orders `a[i]` and `a[j]`, written with selects instead of a branch on the
+/// comparison.
+#[inline(always)]
+fn compare_exchange<T: PartialOrd + Copy>(a: &mut [T], i: usize, j: usize) {
+    let (x, y) = (a[i], a[j]);
+    let swap = lt(&y, &x);
+    a[i] = if swap { y } else { x };
+    a[j] = if swap { x } else { y };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{patterns, XorShift};
+
+    // 0-1 principle: a network sorts everything iff it sorts all bit vectors
+    fn check_network(n: usize) {
+        for bits in 0..1u32 << n {
+            let mut v: Vec<u32> = (0..n).map(|i| bits >> i & 1).collect();
+            sort_small(&mut v);
+            assert!(v.windows(2).all(|w| w[0] <= w[1]), "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn test_networks() {
+        for (n, network) in NETWORKS.iter().enumerate() {
+            for &(i, j) in network.iter() {
+                assert!(i < j && (j as usize) < n);
+            }
+            check_network(n);
+        }
+    }
+
+    #[test]
+    fn test_sort_network() {
+        let mut a = [3.0, -1.0, 2.5, 0.0];
+        sort_network(&mut a);
+        assert_eq!(a, [-1.0, 0.0, 2.5, 3.0]);
+
+        let mut a: [u32; 0] = [];
+        sort_network(&mut a);
+
+        let mut rng = XorShift(0x4e7);
+        for _ in 0..100 {
+            let mut a = [0u64; 16];
+            for x in a.iter_mut() {
+                *x = rng.below(10);
+            }
+            let mut expected = a;
+            expected.sort();
+            sort_network(&mut a);
+            assert_eq!(a, expected);
+        }
+    }
+
+    #[test]
+    fn test_power_of_two_sorts() {
+        let mut rng = XorShift(0xb170);
+
+        for &n in &[0, 1, 2, 4, 32, 1024] {
+            for v in patterns(&mut rng, n) {
+                let mut expected = v.clone();
+                expected.sort();
+
+                let mut a = v.clone();
+                bitonic_sort(&mut a);
+                assert_eq!(a, expected);
+
+                let mut a = v.clone();
+                odd_even_merge_sort(&mut a);
+                assert_eq!(a, expected);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bitonic_sort_not_power_of_two() {
+        bitonic_sort(&mut [3, 2, 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_odd_even_merge_sort_not_power_of_two() {
+        odd_even_merge_sort(&mut [5, 4, 3, 2, 1, 0]);
+    }
+}
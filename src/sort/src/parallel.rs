@@ -0,0 +1,362 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Multi-threaded sorts built on `std::thread::scope`.
+
+use super::{
+    choose_pivot, heap_sort, log2, lt, merge, merge_sort_part, partition3, quick_sort,
+    quick_sort_part,
+};
+use std::sync::{Condvar, Mutex, PoisonError};
+use std::thread;
+
+const DEFAULT_CUTOFF: usize = 1 << 13;
+
+/// Configuration of the parallel sorts.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::ParSort;
+///
+/// let mut v: Vec<u32> = (0..100_000).rev().collect();
+/// ParSort::new().threads(4).cutoff(1000).quick_sort(&mut v);
+/// assert!(v.windows(2).all(|w| w[0] <= w[1]));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ParSort {
+    threads: usize,
+    cutoff: usize,
+}
+
+impl ParSort {
+    /// Uses all available cores and a sequential cutoff of 8192 elements.
+    pub fn new() -> ParSort {
+        let threads = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+
+        ParSort {
+            threads,
+            cutoff: DEFAULT_CUTOFF,
+        }
+    }
+
+    /// Sets the number of threads, `0` is treated as `1`.
+    pub fn threads(mut self, threads: usize) -> ParSort {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Sets the length below which slices are sorted sequentially.
+    pub fn cutoff(mut self, cutoff: usize) -> ParSort {
+        self.cutoff = cutoff.max(1);
+        self
+    }
+
+    /// Stable merge sort: the halves are sorted and merged in parallel until
+    /// every thread has work, then `merge_sort` takes over.
+    pub fn merge_sort<T>(&self, a: &mut [T])
+    where
+        T: PartialOrd + Copy + Send + Sync,
+    {
+        let n = a.len();
+
+        if n <= 1 {
+            return;
+        }
+
+        let mut buf = a.to_vec();
+        merge_sort_part_par(a, &mut buf, self.threads, self.cutoff);
+    }
+
+    /// Unstable quick sort: partitions are handed out to a pool of scoped
+    /// worker threads, slices below the cutoff are finished with `quick_sort`.
+    pub fn quick_sort<T>(&self, a: &mut [T])
+    where
+        T: PartialOrd + Copy + Send,
+    {
+        let n = a.len();
+
+        if self.threads <= 1 || n <= self.cutoff {
+            quick_sort(a);
+            return;
+        }
+
+        let pool = Pool {
+            state: Mutex::new(State {
+                tasks: vec![(a, 2 * log2(n))],
+                pending: 1,
+            }),
+            cond: Condvar::new(),
+        };
+
+        thread::scope(|s| {
+            for _ in 0..self.threads {
+                s.spawn(|| pool.work(self.cutoff));
+            }
+        });
+    }
+}
+
+impl Default for ParSort {
+    fn default() -> ParSort {
+        ParSort::new()
+    }
+}
+
+/// Parallel stable merge sort with the default `ParSort` configuration.
+pub fn par_merge_sort<T: PartialOrd + Copy + Send + Sync>(a: &mut [T]) {
+    ParSort::new().merge_sort(a)
+}
+
+/// Parallel unstable quick sort with the default `ParSort` configuration.
+pub fn par_quick_sort<T: PartialOrd + Copy + Send>(a: &mut [T]) {
+    ParSort::new().quick_sort(a)
+}
+
+fn merge_sort_part_par<T>(a: &mut [T], buf: &mut [T], threads: usize, cutoff: usize)
+where
+    T: PartialOrd + Copy + Send + Sync,
+{
+    let n = a.len();
+
+    if threads <= 1 || n <= cutoff {
+        merge_sort_part(a, buf);
+        return;
+    }
+
+    let mid = n / 2;
+    {
+        let (a_left, a_right) = a.split_at_mut(mid);
+        let (buf_left, buf_right) = buf.split_at_mut(mid);
+        let left_threads = threads / 2;
+        thread::scope(|s| {
+            s.spawn(|| merge_sort_part_par(a_left, buf_left, left_threads, cutoff));
+            merge_sort_part_par(a_right, buf_right, threads - left_threads, cutoff);
+        });
+    }
+
+    if !lt(&a[mid], &a[mid - 1]) {
+        return;
+    }
+
+    buf.copy_from_slice(a);
+    let (left, right) = buf.split_at(mid);
+    merge_par(left, right, a, threads, cutoff);
+}
+
+/// Splits the longer input at its middle element and the shorter one at the
+/// matching position, then merges both halves concurrently.
+fn merge_par<T>(left: &[T], right: &[T], out: &mut [T], threads: usize, cutoff: usize)
+where
+    T: PartialOrd + Copy + Send + Sync,
+{
+    if threads <= 1 || out.len() <= cutoff || left.is_empty() || right.is_empty() {
+        merge(left, right, out);
+        return;
+    }
+
+    // equal elements of `left` have to stay in front of those of `right`
+    let (i, j) = if left.len() >= right.len() {
+        let i = left.len() / 2;
+        let key = left[i];
+        (i, right.partition_point(|x| *x < key))
+    } else {
+        let j = right.len() / 2;
+        let key = right[j];
+        (left.partition_point(|x| !lt(&key, x)), j)
+    };
+
+    let (left_lo, left_hi) = left.split_at(i);
+    let (right_lo, right_hi) = right.split_at(j);
+    let (out_lo, out_hi) = out.split_at_mut(i + j);
+    let lo_threads = threads / 2;
+
+    thread::scope(|s| {
+        s.spawn(|| merge_par(left_lo, right_lo, out_lo, lo_threads, cutoff));
+        merge_par(left_hi, right_hi, out_hi, threads - lo_threads, cutoff);
+    });
+}
+
+struct State<'a, T: 'a> {
+    // slices with their remaining depth limit
+    tasks: Vec<(&'a mut [T], usize)>,
+    // tasks queued or being worked on
+    pending: usize,
+}
+
+struct Pool<'a, T: 'a> {
+    state: Mutex<State<'a, T>>,
+    cond: Condvar,
+}
+
+impl<'a, T: PartialOrd + Copy + Send> Pool<'a, T> {
+    fn work(&self, cutoff: usize) {
+        while let Some((task, depth_limit)) = self.next_task() {
+            let _done = Done(self);
+            self.run(task, depth_limit, cutoff);
+        }
+    }
+
+    fn finish_task(&self) {
+        // a panicking worker may have poisoned the lock, the count is still
+        // consistent since it is only changed under the lock
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.pending -= 1;
+        if state.pending == 0 {
+            self.cond.notify_all();
+        }
+    }
+
+    /// Blocks until a task is available, returns `None` once all work is
+    /// done.
+    fn next_task(&self) -> Option<(&'a mut [T], usize)> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if let Some(task) = state.tasks.pop() {
+                return Some(task);
+            }
+
+            if state.pending == 0 {
+                return None;
+            }
+
+            state = self
+                .cond
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Partitions `a` until it is below the cutoff, sharing the larger side
+    /// of each partition with the other workers. Like `quick_sort`, falls
+    /// back to heap sort once the depth limit is used up.
+    fn run(&self, mut a: &'a mut [T], mut depth_limit: usize, cutoff: usize) {
+        while a.len() > cutoff {
+            if depth_limit == 0 {
+                heap_sort(a);
+                return;
+            }
+            depth_limit -= 1;
+
+            let pivot = a[choose_pivot(a)];
+            let (less, greater) = partition3(a, pivot);
+
+            let tmp = a;
+            let (left, rest) = tmp.split_at_mut(less);
+            let right = &mut rest[greater - less..];
+            let (small, large) = if left.len() < right.len() {
+                (left, right)
+            } else {
+                (right, left)
+            };
+
+            if large.len() > 1 {
+                let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+                state.tasks.push((large, depth_limit));
+                state.pending += 1;
+                self.cond.notify_one();
+            }
+
+            a = small;
+        }
+
+        quick_sort_part(a, depth_limit);
+    }
+}
+
+/// Marks the current task as finished when dropped, even when sorting it
+/// panicked, so that the other workers do not wait for it forever.
+struct Done<'p, 'a: 'p, T: 'a + PartialOrd + Copy + Send>(&'p Pool<'a, T>);
+
+impl<'p, 'a, T: PartialOrd + Copy + Send> Drop for Done<'p, 'a, T> {
+    fn drop(&mut self) {
+        self.0.finish_task();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{check_stable, patterns, XorShift};
+
+    #[test]
+    fn test_par_merge_sort() {
+        let mut v = vec![4, 3, 5, 1, 2];
+        par_merge_sort(&mut v);
+        assert_eq!(v, vec![1, 2, 3, 4, 5]);
+
+        let mut rng = XorShift(0xabcd);
+        for &threads in &[1, 2, 3, 8] {
+            let sorter = ParSort::new().threads(threads).cutoff(100);
+            for mut v in patterns(&mut rng, 50_000) {
+                let mut expected = v.clone();
+                expected.sort();
+                sorter.merge_sort(&mut v);
+                assert_eq!(v, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_par_merge_sort_stable() {
+        let mut rng = XorShift(3);
+        let sorter = ParSort::new().threads(4).cutoff(64);
+        for keys in patterns(&mut rng, 20_000) {
+            check_stable(&keys, |v| sorter.merge_sort(v));
+        }
+    }
+
+    #[test]
+    fn test_par_quick_sort() {
+        let mut v = vec![4, 3, 5, 1, 2];
+        par_quick_sort(&mut v);
+        assert_eq!(v, vec![1, 2, 3, 4, 5]);
+
+        let mut rng = XorShift(0xdcba);
+        for &threads in &[1, 2, 3, 8] {
+            let sorter = ParSort::new().threads(threads).cutoff(100);
+            for mut v in patterns(&mut rng, 50_000) {
+                let mut expected = v.clone();
+                expected.sort();
+                sorter.quick_sort(&mut v);
+                assert_eq!(v, expected);
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Bomb(u32);
+
+    impl PartialOrd for Bomb {
+        fn partial_cmp(&self, other: &Bomb) -> Option<::std::cmp::Ordering> {
+            if self.0 == 0 || other.0 == 0 {
+                panic!("boom");
+            }
+            self.0.partial_cmp(&other.0)
+        }
+    }
+
+    #[test]
+    fn test_par_quick_sort_panic() {
+        // the panic reaches the caller instead of leaving the others waiting
+        let mut v: Vec<Bomb> = (0..100_000).map(|i| Bomb(i * 7919 % 100_003)).collect();
+        let result = ::std::panic::catch_unwind(move || {
+            ParSort::new().threads(4).cutoff(100).quick_sort(&mut v);
+        });
+        assert!(result.is_err());
+    }
+}
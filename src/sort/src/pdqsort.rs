@@ -0,0 +1,461 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pattern-defeating quicksort.
+//!
+//! reference:
+//!     https://github.com/orlp/pdqsort
+//!     BlockQuicksort: How Branch Mispredictions don't affect Quicksort
+//!     (Edelkamp, Weiß)
+
+use super::{heap_sort, insertion_sort_interval, lt};
+use std::cmp;
+
+// slices up to this length are sorted with insertion sort
+const MAX_INSERTION: usize = 20;
+// number of elements scanned per block by the branchless partition
+const BLOCK: usize = 128;
+
+/// Unstable pattern-defeating quicksort.
+///
+/// Runs in O(n) on sorted, reversed and many kinds of almost sorted input,
+/// O(n log k) with k distinct values and O(n log n) in the worst case.
+pub fn pdq_sort<T: PartialOrd + Copy>(a: &mut [T]) {
+    let n = a.len();
+
+    if n <= 1 {
+        return;
+    }
+
+    let limit = (usize::BITS - n.leading_zeros()) as usize;
+    recurse(a, None, limit);
+}
+
+fn recurse<T: PartialOrd + Copy>(mut v: &mut [T], mut pred: Option<T>, mut limit: usize) {
+    let mut was_balanced = true;
+    let mut was_partitioned = true;
+
+    loop {
+        let len = v.len();
+
+        if len <= MAX_INSERTION {
+            if len > 1 {
+                insertion_sort_interval(v, 1);
+            }
+            return;
+        }
+
+        // too many bad pivot choices, guarantee O(n log n)
+        if limit == 0 {
+            heap_sort(v);
+            return;
+        }
+
+        if !was_balanced {
+            break_patterns(v);
+            limit -= 1;
+        }
+
+        let (pivot, likely_sorted) = choose_pivot(v);
+
+        // the last partition was balanced and did not move anything, and the
+        // pivot sample looks ordered: try to finish with a few insertions
+        if was_balanced && was_partitioned && likely_sorted && partial_insertion_sort(v) {
+            return;
+        }
+
+        // the pivot equals the predecessor of this slice, so every element
+        // not greater than it is already in place
+        if let Some(p) = pred {
+            if !lt(&p, &v[pivot]) {
+                let mid = partition_equal(v, pivot);
+                let tmp = v;
+                v = &mut tmp[mid..];
+                continue;
+            }
+        }
+
+        let (mid, partitioned) = partition(v, pivot);
+        was_balanced = cmp::min(mid, len - mid) >= len / 8;
+        was_partitioned = partitioned;
+
+        let tmp = v;
+        let (left, right) = tmp.split_at_mut(mid);
+        let (pivot, right) = right.split_at_mut(1);
+        let pivot = pivot[0];
+
+        if left.len() < right.len() {
+            recurse(left, pred, limit);
+            v = right;
+            pred = Some(pivot);
+        } else {
+            recurse(right, Some(pivot), limit);
+            v = left;
+        }
+    }
+}
+
+/// Partitions `v` around `v[pivot]`.
+///
+/// Returns the final index of the pivot and whether the slice was already
+/// partitioned.
+fn partition<T: PartialOrd + Copy>(v: &mut [T], pivot: usize) -> (usize, bool) {
+    v.swap(0, pivot);
+    let p = v[0];
+
+    let (mid, was_partitioned) = {
+        let rest = &mut v[1..];
+        let len = rest.len();
+
+        // skip the prefix and suffix which are already in place
+        let mut l = 0;
+        let mut r = len;
+        while l < r && rest[l] < p {
+            l += 1;
+        }
+        while l < r && !lt(&rest[r - 1], &p) {
+            r -= 1;
+        }
+
+        (l + partition_in_blocks(&mut rest[l..r], p), l >= r)
+    };
+
+    v.swap(0, mid);
+    (mid, was_partitioned)
+}
+
+/// BlockQuicksort partition: element offsets that belong to the other side
+/// are collected without branching, then swapped in pairs.
+///
+/// Returns the number of elements less than `pivot`.
+fn partition_in_blocks<T: PartialOrd + Copy>(v: &mut [T], pivot: T) -> usize {
+    let mut l = 0;
+    let mut r = v.len();
+
+    let mut offsets_l = [0u8; BLOCK];
+    let mut block_l = BLOCK;
+    let mut start_l = 0;
+    let mut end_l = 0;
+
+    let mut offsets_r = [0u8; BLOCK];
+    let mut block_r = BLOCK;
+    let mut start_r = 0;
+    let mut end_r = 0;
+
+    loop {
+        let is_done = r - l <= 2 * BLOCK;
+
+        if is_done {
+            // size the last blocks so that together they cover [l, r)
+            let mut rem = r - l;
+            if start_l < end_l || start_r < end_r {
+                rem -= BLOCK;
+            }
+
+            if start_l < end_l {
+                block_r = rem;
+            } else if start_r < end_r {
+                block_l = rem;
+            } else {
+                block_l = rem / 2;
+                block_r = rem - block_l;
+            }
+        }
+
+        if start_l == end_l {
+            start_l = 0;
+            end_l = 0;
+            for i in 0..block_l {
+                offsets_l[end_l] = i as u8;
+                end_l += !lt(&v[l + i], &pivot) as usize;
+            }
+        }
+
+        if start_r == end_r {
+            start_r = 0;
+            end_r = 0;
+            for i in 0..block_r {
+                offsets_r[end_r] = i as u8;
+                end_r += (v[r - 1 - i] < pivot) as usize;
+            }
+        }
+
+        let count = cmp::min(end_l - start_l, end_r - start_r);
+        for k in 0..count {
+            let i = l + offsets_l[start_l + k] as usize;
+            let j = r - 1 - offsets_r[start_r + k] as usize;
+            v.swap(i, j);
+        }
+        start_l += count;
+        start_r += count;
+
+        if start_l == end_l {
+            l += block_l;
+        }
+
+        if start_r == end_r {
+            r -= block_r;
+        }
+
+        if is_done {
+            break;
+        }
+    }
+
+    // move the remaining misplaced elements of the unfinished block to the
+    // boundary
+    if start_l < end_l {
+        while start_l < end_l {
+            end_l -= 1;
+            v.swap(l + offsets_l[end_l] as usize, r - 1);
+            r -= 1;
+        }
+        r
+    } else if start_r < end_r {
+        while start_r < end_r {
+            end_r -= 1;
+            v.swap(l, r - 1 - offsets_r[end_r] as usize);
+            l += 1;
+        }
+        l
+    } else {
+        l
+    }
+}
+
+/// Moves the elements equal to `v[pivot]` to the front, assuming no element
+/// is smaller than the pivot.
+///
+/// Returns the number of elements equal to the pivot.
+fn partition_equal<T: PartialOrd + Copy>(v: &mut [T], pivot: usize) -> usize {
+    v.swap(0, pivot);
+    let p = v[0];
+
+    let rest = &mut v[1..];
+    let mut l = 0;
+    let mut r = rest.len();
+
+    loop {
+        while l < r && !lt(&p, &rest[l]) {
+            l += 1;
+        }
+        while l < r && p < rest[r - 1] {
+            r -= 1;
+        }
+
+        if l >= r {
+            break;
+        }
+
+        r -= 1;
+        rest.swap(l, r);
+        l += 1;
+    }
+
+    l + 1
+}
+
+/// Sorts `v` if it only has a few out-of-order elements.
+///
+/// Returns `true` if the slice is sorted afterwards.
+fn partial_insertion_sort<T: PartialOrd + Copy>(v: &mut [T]) -> bool {
+    const MAX_STEPS: usize = 5;
+    const SHORTEST_SHIFTING: usize = 50;
+
+    let len = v.len();
+    let mut i = 1;
+
+    for _ in 0..MAX_STEPS {
+        while i < len && !lt(&v[i], &v[i - 1]) {
+            i += 1;
+        }
+
+        if i == len {
+            return true;
+        }
+
+        // not worth shifting elements on short slices
+        if len < SHORTEST_SHIFTING {
+            return false;
+        }
+
+        v.swap(i - 1, i);
+        shift_tail(&mut v[..i]);
+        shift_head(&mut v[i..]);
+    }
+
+    false
+}
+
+// inserts the last element into the sorted prefix
+fn shift_tail<T: PartialOrd + Copy>(v: &mut [T]) {
+    let len = v.len();
+    if len < 2 {
+        return;
+    }
+
+    let x = v[len - 1];
+    let mut j = len - 1;
+    while j > 0 && x < v[j - 1] {
+        v[j] = v[j - 1];
+        j -= 1;
+    }
+    v[j] = x;
+}
+
+// inserts the first element into the sorted suffix
+fn shift_head<T: PartialOrd + Copy>(v: &mut [T]) {
+    let len = v.len();
+    if len < 2 {
+        return;
+    }
+
+    let x = v[0];
+    let mut j = 0;
+    while j + 1 < len && v[j + 1] < x {
+        v[j] = v[j + 1];
+        j += 1;
+    }
+    v[j] = x;
+}
+
+/// Swaps a few elements around the middle to break up patterns that lead to
+/// unbalanced partitions.
+fn break_patterns<T>(v: &mut [T]) {
+    let len = v.len();
+    if len < 8 {
+        return;
+    }
+
+    let mut seed = len as u64;
+    let mut gen = || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+
+    let modulus = len.next_power_of_two();
+    let pos = len / 4 * 2;
+
+    for i in 0..3 {
+        let mut other = (gen() as usize) & (modulus - 1);
+        if other >= len {
+            other -= len;
+        }
+        v.swap(pos - 1 + i, other);
+    }
+}
+
+/// Chooses a pivot by median of three, or pseudo-median of nine on longer
+/// slices.
+///
+/// Returns the pivot index and whether the slice is likely already sorted.
+/// If the samples looked reversed the slice gets reversed in place.
+fn choose_pivot<T: PartialOrd>(v: &mut [T]) -> (usize, bool) {
+    const SHORTEST_MEDIAN_OF_MEDIANS: usize = 50;
+    const MAX_SWAPS: usize = 4 * 3;
+
+    let len = v.len();
+
+    let mut a = len / 4;
+    let mut b = len / 4 * 2;
+    let mut c = len / 4 * 3;
+    let mut swaps = 0;
+
+    if len >= 8 {
+        {
+            let mut sort2 = |a: &mut usize, b: &mut usize| {
+                if v[*b] < v[*a] {
+                    std::mem::swap(a, b);
+                    swaps += 1;
+                }
+            };
+
+            let mut sort3 = |a: &mut usize, b: &mut usize, c: &mut usize| {
+                sort2(a, b);
+                sort2(b, c);
+                sort2(a, b);
+            };
+
+            if len >= SHORTEST_MEDIAN_OF_MEDIANS {
+                let mut sort_adjacent = |a: &mut usize| {
+                    let tmp = *a;
+                    sort3(&mut (tmp - 1), a, &mut (tmp + 1));
+                };
+
+                sort_adjacent(&mut a);
+                sort_adjacent(&mut b);
+                sort_adjacent(&mut c);
+            }
+
+            sort3(&mut a, &mut b, &mut c);
+        }
+    }
+
+    if swaps < MAX_SWAPS {
+        (b, swaps == 0)
+    } else {
+        // the samples were in descending order, so the slice probably is too
+        v.reverse();
+        (len - 1 - b, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{patterns, XorShift};
+
+    #[test]
+    fn test_pdq_sort() {
+        let mut v = vec![4, 3, 5, 1, 2];
+        pdq_sort(&mut v);
+        assert_eq!(v, vec![1, 2, 3, 4, 5]);
+
+        let mut v = vec![5, 4, 3, 2, 1];
+        pdq_sort(&mut v);
+        assert_eq!(v, vec![1, 2, 3, 4, 5]);
+
+        let mut v: Vec<i32> = vec![];
+        pdq_sort(&mut v);
+        assert_eq!(v, vec![]);
+    }
+
+    #[test]
+    fn test_pdq_sort_random() {
+        let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+
+        for &n in &[2, 10, 21, 50, 100, 257, 1000, 10_000, 100_000] {
+            for mut v in patterns(&mut rng, n) {
+                let mut expected = v.clone();
+                expected.sort();
+                pdq_sort(&mut v);
+                assert_eq!(v, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_partition_in_blocks() {
+        let mut rng = XorShift(42);
+
+        for &n in &[0, 1, 5, 128, 255, 256, 257, 1000] {
+            let mut v: Vec<u32> = (0..n).map(|_| rng.below(100) as u32).collect();
+            let mid = partition_in_blocks(&mut v, 50);
+            assert!(v[..mid].iter().all(|&x| x < 50));
+            assert!(v[mid..].iter().all(|&x| x >= 50));
+        }
+    }
+}
@@ -0,0 +1,401 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Selection: k-th element, partial sort, top-k and quantiles.
+
+use super::{choose_pivot, insertion_sort, log2, partition3, quick_sort, INSERTION_SORT_THRESHOLD};
+use std::cmp::Ordering;
+
+/// Reorders `a` so that the element at `k` is the one that would be there if
+/// `a` was sorted, with no greater element before it and no smaller element
+/// after it.
+///
+/// Uses introselect: quickselect with median-of-three pivots, switching to
+/// median-of-medians pivots after `2 * log2(n)` rounds, so the worst case is
+/// O(n).
+///
+/// Returns the slice before `k`, the element at `k` and the slice after it.
+///
+/// # Panics
+///
+/// Panics if `k >= a.len()`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::select_nth_unstable;
+///
+/// let mut v = vec![5, 1, 4, 2, 3];
+/// let (_, median, _) = select_nth_unstable(&mut v, 2);
+/// assert_eq!(*median, 3);
+/// ```
+pub fn select_nth_unstable<T: PartialOrd + Copy>(
+    a: &mut [T],
+    k: usize,
+) -> (&mut [T], &mut T, &mut [T]) {
+    let n = a.len();
+    assert!(k < n, "index {} out of range for slice of length {}", k, n);
+
+    select(a, k, 2 * log2(n));
+
+    let (left, rest) = a.split_at_mut(k);
+    let (nth, right) = rest.split_first_mut().unwrap();
+    (left, nth, right)
+}
+
+fn select<T: PartialOrd + Copy>(mut v: &mut [T], mut k: usize, mut limit: usize) {
+    loop {
+        if v.len() <= INSERTION_SORT_THRESHOLD {
+            insertion_sort(v);
+            return;
+        }
+
+        let pivot = if limit == 0 {
+            median_of_medians(v)
+        } else {
+            limit -= 1;
+            choose_pivot(v)
+        };
+        let pivot = v[pivot];
+        let (less, greater) = partition3(v, pivot);
+
+        let tmp = v;
+        if k < less {
+            v = &mut tmp[..less];
+        } else if k >= greater {
+            v = &mut tmp[greater..];
+            k -= greater;
+        } else {
+            return;
+        }
+    }
+}
+
+/// Returns the index of a pivot that has at least 3/10 of the elements on
+/// either side: the median of the medians of groups of five.
+fn median_of_medians<T: PartialOrd + Copy>(v: &mut [T]) -> usize {
+    let n = v.len();
+
+    if n <= 5 {
+        insertion_sort(v);
+        return n / 2;
+    }
+
+    // collect the group medians at the front
+    let groups = n / 5;
+    for g in 0..groups {
+        insertion_sort(&mut v[g * 5..g * 5 + 5]);
+        v.swap(g, g * 5 + 2);
+    }
+
+    let mid = groups / 2;
+    select(&mut v[..groups], mid, 0);
+    mid
+}
+
+/// Rearranges `a` so that `a[..k]` holds its `k` smallest elements in
+/// ascending order; the order of the rest is unspecified.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::partial_sort;
+///
+/// let mut v = vec![9, 2, 7, 4, 5, 1, 8];
+/// partial_sort(&mut v, 3);
+/// assert_eq!(&v[..3], &[1, 2, 4]);
+/// ```
+pub fn partial_sort<T: PartialOrd + Copy>(a: &mut [T], k: usize) {
+    let n = a.len();
+
+    if k == 0 || n <= 1 {
+        return;
+    }
+
+    if k >= n {
+        quick_sort(a);
+        return;
+    }
+
+    select_nth_unstable(a, k - 1);
+    quick_sort(&mut a[..k - 1]);
+}
+
+/// Returns the `k` greatest items of `iter` in descending order.
+///
+/// Keeps a min-heap of at most `k` items, so it takes O(n log k) time and
+/// O(k) space and works on streams of any length.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::top_k;
+///
+/// assert_eq!(top_k(vec![3, 9, 1, 7, 5], 2), vec![9, 7]);
+/// ```
+pub fn top_k<I>(iter: I, k: usize) -> Vec<I::Item>
+where
+    I: IntoIterator,
+    I::Item: PartialOrd,
+{
+    top_k_by(iter, k, |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+}
+
+/// Returns the `k` greatest items of `iter` according to `compare`, in
+/// descending order.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::top_k_by;
+///
+/// let words = vec!["fig", "banana", "kiwi", "cherry"];
+/// let longest = top_k_by(words, 2, |a, b| a.len().cmp(&b.len()));
+/// assert_eq!(longest.len(), 2);
+/// assert!(longest.iter().all(|w| w.len() == 6));
+/// ```
+pub fn top_k_by<I, F>(iter: I, k: usize, mut compare: F) -> Vec<I::Item>
+where
+    I: IntoIterator,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    let mut heap = Vec::with_capacity(k);
+
+    if k == 0 {
+        return heap;
+    }
+
+    for x in iter {
+        if heap.len() < k {
+            heap.push(x);
+            let i = heap.len() - 1;
+            sift_up_min(&mut heap, i, &mut compare);
+        } else if compare(&x, &heap[0]) == Ordering::Greater {
+            heap[0] = x;
+            sift_down_min(&mut heap, 0, &mut compare);
+        }
+    }
+
+    heap.sort_by(|a, b| compare(b, a));
+    heap
+}
+
+fn sift_up_min<T, F>(heap: &mut [T], mut i: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    while i > 0 {
+        let parent = (i - 1) / 2;
+        if compare(&heap[i], &heap[parent]) == Ordering::Less {
+            heap.swap(i, parent);
+            i = parent;
+        } else {
+            break;
+        }
+    }
+}
+
+fn sift_down_min<T, F>(heap: &mut [T], mut i: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let n = heap.len();
+    loop {
+        let mut child = 2 * i + 1;
+        if child >= n {
+            break;
+        }
+
+        if child + 1 < n && compare(&heap[child + 1], &heap[child]) == Ordering::Less {
+            child += 1;
+        }
+
+        if compare(&heap[child], &heap[i]) == Ordering::Less {
+            heap.swap(i, child);
+            i = child;
+        } else {
+            break;
+        }
+    }
+}
+
+/// Median of `a`, the mean of the two middle values for even lengths.
+///
+/// Returns `None` if `a` is empty. NaNs are not supported.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::median;
+///
+/// assert_eq!(median(&[3.0, 1.0, 2.0]), Some(2.0));
+/// assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), Some(2.5));
+/// assert_eq!(median(&[]), None);
+/// ```
+pub fn median(a: &[f64]) -> Option<f64> {
+    quantiles(a, &[0.5]).map(|q| q[0])
+}
+
+/// Quantiles of `a` at each of the probabilities `qs`, interpolating
+/// linearly between the closest ranks.
+///
+/// Returns `None` if `a` is empty. NaNs are not supported.
+///
+/// # Panics
+///
+/// Panics if a probability is outside `[0, 1]`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate sort;
+/// use sort::quantiles;
+///
+/// let a = [1.0, 2.0, 3.0, 4.0, 5.0];
+/// assert_eq!(quantiles(&a, &[0.0, 0.25, 0.9]), Some(vec![1.0, 2.0, 4.6]));
+/// ```
+pub fn quantiles(a: &[f64], qs: &[f64]) -> Option<Vec<f64>> {
+    let n = a.len();
+
+    if n == 0 {
+        return None;
+    }
+
+    // selections only move elements around, so one copy serves every query
+    let mut v = a.to_vec();
+    let quantiles = qs
+        .iter()
+        .map(|&q| {
+            assert!((0.0..=1.0).contains(&q), "quantile {} is outside [0, 1]", q);
+
+            let h = (n - 1) as f64 * q;
+            let lo = h.floor() as usize;
+            let (_, &mut x, right) = select_nth_unstable(&mut v, lo);
+            if lo + 1 < n && h > lo as f64 {
+                let y = right.iter().cloned().fold(f64::INFINITY, f64::min);
+                x + (h - lo as f64) * (y - x)
+            } else {
+                x
+            }
+        })
+        .collect();
+
+    Some(quantiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{patterns, XorShift};
+
+    #[test]
+    fn test_select_nth_unstable() {
+        let mut rng = XorShift(0x5e1ec7);
+
+        for &n in &[1, 2, 17, 100, 1000, 10_000] {
+            for v in patterns(&mut rng, n) {
+                let mut sorted = v.clone();
+                sorted.sort();
+
+                for _ in 0..10 {
+                    let k = rng.below(n as u64) as usize;
+                    let mut a = v.clone();
+                    let (left, nth, right) = select_nth_unstable(&mut a, k);
+                    assert_eq!(*nth, sorted[k]);
+                    assert!(left.iter().all(|x| x <= nth));
+                    assert!(right.iter().all(|x| x >= nth));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_select_median_of_medians() {
+        let mut rng = XorShift(0xa11ce);
+
+        for v in patterns(&mut rng, 5000) {
+            let mut sorted = v.clone();
+            sorted.sort();
+
+            for &k in &[0, 1, 2499, 4998, 4999] {
+                let mut a = v.clone();
+                select(&mut a, k, 0);
+                assert_eq!(a[k], sorted[k]);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_select_nth_unstable_out_of_range() {
+        select_nth_unstable(&mut [1, 2, 3], 3);
+    }
+
+    #[test]
+    fn test_partial_sort() {
+        let mut rng = XorShift(0xbeef);
+
+        for v in patterns(&mut rng, 1000) {
+            let mut sorted = v.clone();
+            sorted.sort();
+
+            for &k in &[0, 1, 10, 999, 1000, 2000] {
+                let mut a = v.clone();
+                partial_sort(&mut a, k);
+                let k = k.min(1000);
+                assert_eq!(&a[..k], &sorted[..k]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_top_k() {
+        assert_eq!(top_k(vec![3, 9, 1, 7, 5], 0), Vec::<i32>::new());
+        assert_eq!(top_k(vec![3, 9, 1, 7, 5], 3), vec![9, 7, 5]);
+        assert_eq!(top_k(vec![3, 9], 3), vec![9, 3]);
+
+        let mut rng = XorShift(0xcafe);
+        let v: Vec<u64> = (0..10_000).map(|_| rng.below(1000)).collect();
+        let mut sorted = v.clone();
+        sorted.sort_by(|a, b| b.cmp(a));
+        assert_eq!(top_k(v.iter().cloned(), 100), &sorted[..100]);
+
+        // reversed order gives the smallest items in ascending order
+        let smallest: Vec<u64> = top_k_by(v.iter(), 5, |a, b| b.cmp(a))
+            .into_iter()
+            .cloned()
+            .collect();
+        sorted.reverse();
+        assert_eq!(smallest, &sorted[..5]);
+    }
+
+    #[test]
+    fn test_quantiles() {
+        assert_eq!(median(&[]), None);
+        assert_eq!(median(&[7.0]), Some(7.0));
+        assert_eq!(median(&[5.0, 1.0, 3.0, 2.0, 4.0]), Some(3.0));
+        assert_eq!(median(&[5.0, 1.0, 3.0, 2.0]), Some(2.5));
+
+        let a: Vec<f64> = (0..101).rev().map(|x| x as f64).collect();
+        assert_eq!(
+            quantiles(&a, &[0.0, 0.1, 0.5, 0.995, 1.0]),
+            Some(vec![0.0, 10.0, 50.0, 99.5, 100.0])
+        );
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers shared by the unit tests of this crate.
+
+use std::cmp::Ordering;
+
+pub struct XorShift(pub u64);
+
+impl XorShift {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+/// Inputs of various shapes used to exercise the sorts: random, few distinct
+/// values, sorted, reversed, organ pipe and sorted with a few random swaps.
+pub fn patterns(rng: &mut XorShift, n: usize) -> Vec<Vec<u32>> {
+    let mut out = Vec::new();
+
+    out.push((0..n).map(|_| rng.next() as u32).collect());
+    out.push((0..n).map(|_| rng.below(4) as u32).collect());
+    out.push((0..n as u32).collect());
+    out.push((0..n as u32).rev().collect());
+    out.push(
+        (0..n as u32)
+            .map(|i| if i < n as u32 / 2 { i } else { n as u32 - i })
+            .collect(),
+    );
+
+    let mut almost: Vec<u32> = (0..n as u32).collect();
+    if n > 1 {
+        for _ in 0..(n / 100 + 1) {
+            let i = rng.below(n as u64) as usize;
+            let j = rng.below(n as u64) as usize;
+            almost.swap(i, j);
+        }
+    }
+    out.push(almost);
+
+    out
+}
+
+/// An element ordered by `key` only, `seq` records the original position to
+/// check stability.
+#[derive(Debug, Clone, Copy)]
+pub struct Item {
+    pub key: u32,
+    pub seq: usize,
+}
+
+impl PartialEq for Item {
+    fn eq(&self, other: &Item) -> bool {
+        self.key == other.key
+    }
+}
+
+impl PartialOrd for Item {
+    fn partial_cmp(&self, other: &Item) -> Option<Ordering> {
+        self.key.partial_cmp(&other.key)
+    }
+}
+
+/// Checks that `sort` orders `Item`s like the stable `slice::sort_by_key`.
+pub fn check_stable<F: Fn(&mut [Item])>(keys: &[u32], sort: F) {
+    let mut v: Vec<Item> = keys
+        .iter()
+        .enumerate()
+        .map(|(seq, &k)| Item { key: k % 16, seq })
+        .collect();
+    let mut expected = v.clone();
+    expected.sort_by_key(|x| x.key);
+    sort(&mut v);
+
+    let seqs: Vec<usize> = v.iter().map(|x| x.seq).collect();
+    let expected: Vec<usize> = expected.iter().map(|x| x.seq).collect();
+    assert_eq!(seqs, expected);
+}
@@ -0,0 +1,549 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Timsort.
+//!
+//! reference:
+//!     https://github.com/python/cpython/blob/main/Objects/listsort.txt
+//!     http://envisage-project.eu/proving-android-java-and-python-sorting-algorithm-is-broken-and-how-to-fix-it/
+
+use super::{insertion_sort_interval, lt};
+use std::cmp;
+
+// slices shorter than this are sorted with insertion sort only
+const MIN_MERGE: usize = 64;
+// initial number of consecutive wins before switching to galloping mode
+const MIN_GALLOP: usize = 7;
+
+/// Stable adaptive merge sort.
+///
+/// Natural runs are detected (descending runs are reversed), short runs are
+/// extended to `minrun` by insertion sort and merged with galloping.
+pub fn tim_sort<T: PartialOrd + Copy>(a: &mut [T]) {
+    let n = a.len();
+
+    if n <= 1 {
+        return;
+    }
+
+    if n < MIN_MERGE {
+        insertion_sort_interval(a, 1);
+        return;
+    }
+
+    let min_run = min_run_length(n);
+    let mut ts = TimSort {
+        v: a,
+        runs: Vec::new(),
+        buf: Vec::new(),
+        min_gallop: MIN_GALLOP,
+    };
+
+    let mut lo = 0;
+    while lo < n {
+        let mut run_len = count_run_and_make_ascending(&mut ts.v[lo..]);
+
+        if run_len < min_run {
+            let force = cmp::min(min_run, n - lo);
+            insertion_sort_interval(&mut ts.v[lo..lo + force], 1);
+            run_len = force;
+        }
+
+        ts.runs.push(Run {
+            base: lo,
+            len: run_len,
+        });
+        ts.merge_collapse();
+
+        lo += run_len;
+    }
+
+    ts.merge_force_collapse();
+    debug_assert_eq!(ts.runs.len(), 1);
+}
+
+/// Returns a run length in `[MIN_MERGE / 2, MIN_MERGE]` such that `n / minrun`
+/// is a power of two or slightly less than one.
+fn min_run_length(mut n: usize) -> usize {
+    let mut r = 0;
+    while n >= MIN_MERGE {
+        r |= n & 1;
+        n >>= 1;
+    }
+    n + r
+}
+
+/// Returns the length of the run at the beginning of `v`, reversing it if
+/// it is strictly descending.
+fn count_run_and_make_ascending<T: PartialOrd>(v: &mut [T]) -> usize {
+    let n = v.len();
+    if n <= 1 {
+        return n;
+    }
+
+    let mut run = 2;
+    if v[1] < v[0] {
+        // strictly descending, so reversing keeps the sort stable
+        while run < n && v[run] < v[run - 1] {
+            run += 1;
+        }
+        v[..run].reverse();
+    } else {
+        while run < n && !lt(&v[run], &v[run - 1]) {
+            run += 1;
+        }
+    }
+
+    run
+}
+
+#[derive(Clone, Copy)]
+struct Run {
+    base: usize,
+    len: usize,
+}
+
+struct TimSort<'a, T: 'a> {
+    v: &'a mut [T],
+    runs: Vec<Run>,
+    buf: Vec<T>,
+    min_gallop: usize,
+}
+
+impl<'a, T: PartialOrd + Copy> TimSort<'a, T> {
+    /// Merges runs until the stack invariants hold again:
+    ///
+    /// 1. `runs[i - 2].len > runs[i - 1].len + runs[i].len`
+    /// 2. `runs[i - 1].len > runs[i].len`
+    ///
+    /// The invariant is checked on the top four runs, see the reference
+    /// above for why three are not enough.
+    fn merge_collapse(&mut self) {
+        while self.runs.len() > 1 {
+            let r = &self.runs;
+            let mut n = r.len() - 2;
+
+            if (n > 0 && r[n - 1].len <= r[n].len + r[n + 1].len)
+                || (n > 1 && r[n - 2].len <= r[n - 1].len + r[n].len)
+            {
+                if r[n - 1].len < r[n + 1].len {
+                    n -= 1;
+                }
+            } else if r[n].len > r[n + 1].len {
+                break;
+            }
+
+            self.merge_at(n);
+        }
+    }
+
+    fn merge_force_collapse(&mut self) {
+        while self.runs.len() > 1 {
+            let mut n = self.runs.len() - 2;
+            if n > 0 && self.runs[n - 1].len < self.runs[n + 1].len {
+                n -= 1;
+            }
+            self.merge_at(n);
+        }
+    }
+
+    /// Merges `runs[i]` with `runs[i + 1]`.
+    fn merge_at(&mut self, i: usize) {
+        let Run {
+            base: base1,
+            len: len1,
+        } = self.runs[i];
+        let Run {
+            base: base2,
+            len: len2,
+        } = self.runs[i + 1];
+        debug_assert_eq!(base1 + len1, base2);
+
+        self.runs[i].len = len1 + len2;
+        self.runs.remove(i + 1);
+
+        // elements of run1 not greater than run2[0] are already in place
+        let key = self.v[base2];
+        let k = gallop_right(&key, &self.v[base1..base2], 0);
+        let base1 = base1 + k;
+        let len1 = len1 - k;
+        if len1 == 0 {
+            return;
+        }
+
+        // elements of run2 not less than the last of run1 are already in place
+        let key = self.v[base2 - 1];
+        let len2 = gallop_left(&key, &self.v[base2..base2 + len2], len2 - 1);
+        if len2 == 0 {
+            return;
+        }
+
+        let v = &mut self.v[base1..base2 + len2];
+        if len1 <= len2 {
+            merge_lo(v, len1, &mut self.buf, &mut self.min_gallop);
+        } else {
+            merge_hi(v, len1, &mut self.buf, &mut self.min_gallop);
+        }
+    }
+}
+
+/// Merges `v[..mid]` and `v[mid..]` from the front, buffering the left run.
+fn merge_lo<T: PartialOrd + Copy>(
+    v: &mut [T],
+    mid: usize,
+    buf: &mut Vec<T>,
+    min_gallop: &mut usize,
+) {
+    buf.clear();
+    buf.extend_from_slice(&v[..mid]);
+
+    let len = v.len();
+    let len1 = buf.len();
+    let mut i = 0; // cursor in buf (left run)
+    let mut j = mid; // cursor in v (right run)
+    let mut d = 0; // destination
+
+    'outer: while i < len1 && j < len {
+        let mut count1 = 0;
+        let mut count2 = 0;
+
+        // one pair at a time until one run wins consistently
+        loop {
+            if v[j] < buf[i] {
+                v[d] = v[j];
+                d += 1;
+                j += 1;
+                count2 += 1;
+                count1 = 0;
+                if j == len {
+                    break 'outer;
+                }
+            } else {
+                v[d] = buf[i];
+                d += 1;
+                i += 1;
+                count1 += 1;
+                count2 = 0;
+                if i == len1 {
+                    break 'outer;
+                }
+            }
+
+            if count1 >= *min_gallop || count2 >= *min_gallop {
+                break;
+            }
+        }
+
+        // galloping mode
+        loop {
+            let key = v[j];
+            count1 = gallop_right(&key, &buf[i..], 0);
+            if count1 > 0 {
+                v[d..d + count1].copy_from_slice(&buf[i..i + count1]);
+                d += count1;
+                i += count1;
+                if i == len1 {
+                    break 'outer;
+                }
+            }
+            v[d] = v[j];
+            d += 1;
+            j += 1;
+            if j == len {
+                break 'outer;
+            }
+
+            let key = buf[i];
+            count2 = gallop_left(&key, &v[j..], 0);
+            if count2 > 0 {
+                v.copy_within(j..j + count2, d);
+                d += count2;
+                j += count2;
+                if j == len {
+                    break 'outer;
+                }
+            }
+            v[d] = buf[i];
+            d += 1;
+            i += 1;
+            if i == len1 {
+                break 'outer;
+            }
+
+            if *min_gallop > 1 {
+                *min_gallop -= 1;
+            }
+
+            if count1 < MIN_GALLOP && count2 < MIN_GALLOP {
+                break;
+            }
+        }
+
+        // penalize leaving galloping mode
+        *min_gallop += 2;
+    }
+
+    // the rest of the right run is already in place
+    if i < len1 {
+        v[d..].copy_from_slice(&buf[i..]);
+    }
+}
+
+/// Merges `v[..mid]` and `v[mid..]` from the back, buffering the right run.
+fn merge_hi<T: PartialOrd + Copy>(
+    v: &mut [T],
+    mid: usize,
+    buf: &mut Vec<T>,
+    min_gallop: &mut usize,
+) {
+    buf.clear();
+    buf.extend_from_slice(&v[mid..]);
+
+    let mut i = buf.len(); // end of the unmerged part of buf (right run)
+    let mut j = mid; // end of the unmerged part of the left run
+    let mut d = v.len(); // end of the destination
+
+    'outer: while i > 0 && j > 0 {
+        let mut count1 = 0;
+        let mut count2 = 0;
+
+        loop {
+            if buf[i - 1] < v[j - 1] {
+                d -= 1;
+                j -= 1;
+                v[d] = v[j];
+                count1 += 1;
+                count2 = 0;
+                if j == 0 {
+                    break 'outer;
+                }
+            } else {
+                d -= 1;
+                i -= 1;
+                v[d] = buf[i];
+                count2 += 1;
+                count1 = 0;
+                if i == 0 {
+                    break 'outer;
+                }
+            }
+
+            if count1 >= *min_gallop || count2 >= *min_gallop {
+                break;
+            }
+        }
+
+        loop {
+            let key = buf[i - 1];
+            count1 = j - gallop_right(&key, &v[..j], j - 1);
+            if count1 > 0 {
+                d -= count1;
+                j -= count1;
+                v.copy_within(j..j + count1, d);
+                if j == 0 {
+                    break 'outer;
+                }
+            }
+            d -= 1;
+            i -= 1;
+            v[d] = buf[i];
+            if i == 0 {
+                break 'outer;
+            }
+
+            let key = v[j - 1];
+            count2 = i - gallop_left(&key, &buf[..i], i - 1);
+            if count2 > 0 {
+                d -= count2;
+                i -= count2;
+                v[d..d + count2].copy_from_slice(&buf[i..i + count2]);
+                if i == 0 {
+                    break 'outer;
+                }
+            }
+            d -= 1;
+            j -= 1;
+            v[d] = v[j];
+            if j == 0 {
+                break 'outer;
+            }
+
+            if *min_gallop > 1 {
+                *min_gallop -= 1;
+            }
+
+            if count1 < MIN_GALLOP && count2 < MIN_GALLOP {
+                break;
+            }
+        }
+
+        *min_gallop += 2;
+    }
+
+    // the rest of the left run is already in place
+    if i > 0 {
+        v[d - i..d].copy_from_slice(&buf[..i]);
+    }
+}
+
+/// Returns the leftmost position at which `key` could be inserted into the
+/// sorted slice `a`, searching outwards from `hint` with exponential steps.
+fn gallop_left<T: PartialOrd>(key: &T, a: &[T], hint: usize) -> usize {
+    let n = a.len();
+    debug_assert!(hint < n);
+
+    let mut last_ofs = 0;
+    let mut ofs = 1;
+    let (mut lo, mut hi);
+
+    if a[hint] < *key {
+        // gallop right until a[hint + last_ofs] < key <= a[hint + ofs]
+        let max_ofs = n - hint;
+        while ofs < max_ofs && a[hint + ofs] < *key {
+            last_ofs = ofs;
+            ofs = (ofs << 1) + 1;
+        }
+        ofs = cmp::min(ofs, max_ofs);
+        lo = hint + last_ofs + 1;
+        hi = hint + ofs;
+    } else {
+        // gallop left until a[hint - ofs] < key <= a[hint - last_ofs]
+        let max_ofs = hint + 1;
+        while ofs < max_ofs && !lt(&a[hint - ofs], key) {
+            last_ofs = ofs;
+            ofs = (ofs << 1) + 1;
+        }
+        ofs = cmp::min(ofs, max_ofs);
+        lo = hint + 1 - ofs;
+        hi = hint - last_ofs;
+    }
+
+    while lo < hi {
+        let mid = lo + ((hi - lo) >> 1);
+        if a[mid] < *key {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+
+    lo
+}
+
+/// Returns the rightmost position at which `key` could be inserted into the
+/// sorted slice `a`, searching outwards from `hint` with exponential steps.
+fn gallop_right<T: PartialOrd>(key: &T, a: &[T], hint: usize) -> usize {
+    let n = a.len();
+    debug_assert!(hint < n);
+
+    let mut last_ofs = 0;
+    let mut ofs = 1;
+    let (mut lo, mut hi);
+
+    if *key < a[hint] {
+        // gallop left until a[hint - ofs] <= key < a[hint - last_ofs]
+        let max_ofs = hint + 1;
+        while ofs < max_ofs && *key < a[hint - ofs] {
+            last_ofs = ofs;
+            ofs = (ofs << 1) + 1;
+        }
+        ofs = cmp::min(ofs, max_ofs);
+        lo = hint + 1 - ofs;
+        hi = hint - last_ofs;
+    } else {
+        // gallop right until a[hint + last_ofs] <= key < a[hint + ofs]
+        let max_ofs = n - hint;
+        while ofs < max_ofs && !lt(key, &a[hint + ofs]) {
+            last_ofs = ofs;
+            ofs = (ofs << 1) + 1;
+        }
+        ofs = cmp::min(ofs, max_ofs);
+        lo = hint + last_ofs + 1;
+        hi = hint + ofs;
+    }
+
+    while lo < hi {
+        let mid = lo + ((hi - lo) >> 1);
+        if *key < a[mid] {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+
+    lo
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{check_stable, patterns, XorShift};
+
+    #[test]
+    fn test_tim_sort() {
+        let mut v = vec![4, 3, 5, 1, 2];
+        tim_sort(&mut v);
+        assert_eq!(v, vec![1, 2, 3, 4, 5]);
+
+        let mut v = vec![5, 4, 3, 2, 1];
+        tim_sort(&mut v);
+        assert_eq!(v, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_tim_sort_random() {
+        let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+
+        for &n in &[2, 10, 63, 64, 65, 100, 257, 1000, 10_000, 100_000] {
+            for mut v in patterns(&mut rng, n) {
+                let mut expected = v.clone();
+                expected.sort();
+                tim_sort(&mut v);
+                assert_eq!(v, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tim_sort_stable() {
+        let mut rng = XorShift(7);
+
+        for &n in &[100, 1000, 50_000] {
+            for keys in patterns(&mut rng, n) {
+                check_stable(&keys, tim_sort);
+            }
+        }
+    }
+
+    #[test]
+    fn test_min_run_length() {
+        assert_eq!(min_run_length(63), 63);
+        assert_eq!(min_run_length(64), 32);
+        assert_eq!(min_run_length(65), 33);
+        assert_eq!(min_run_length(2048), 32);
+    }
+
+    #[test]
+    fn test_gallop() {
+        let a = vec![1, 2, 2, 2, 3, 5, 8, 8, 9];
+        for hint in 0..a.len() {
+            assert_eq!(gallop_left(&0, &a, hint), 0);
+            assert_eq!(gallop_left(&2, &a, hint), 1);
+            assert_eq!(gallop_right(&2, &a, hint), 4);
+            assert_eq!(gallop_left(&8, &a, hint), 6);
+            assert_eq!(gallop_right(&8, &a, hint), 8);
+            assert_eq!(gallop_right(&10, &a, hint), 9);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compares SipHash, `FxHasher` and sorting for `two_sum` across inputs.
+//!
+//! Run with `cargo bench --bench hashers`, optionally passing the slice
+//! length (default 100000).
+
+extern crate two_sum;
+
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::time::{Duration, Instant};
+use two_sum::{two_sum_by_sorting, two_sum_with_hasher, All, FxBuildHasher};
+
+const ROUNDS: usize = 5;
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// Inputs with their target, chosen so that few pairs match.
+fn distributions(n: usize) -> Vec<(&'static str, Vec<u64>, u64)> {
+    let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+
+    let random = (0..n).map(|_| rng.next() >> 1).collect();
+    let sequential = (0..n as u64).collect();
+    // equal low bits, the worst case of a multiplicative hash
+    let strided = (0..n as u64).map(|i| i << 32).collect();
+
+    vec![
+        ("random", random, rng.next() >> 1),
+        // beyond the largest pair sum, 2n - 3
+        ("sequential", sequential, 2 * n as u64),
+        ("strided", strided, 1),
+    ]
+}
+
+fn bench<F: Fn() -> usize>(name: &str, f: F) {
+    let mut best = Duration::from_secs(u64::MAX);
+    let mut pairs = 0;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        pairs = f();
+        best = best.min(start.elapsed());
+    }
+
+    println!(
+        "  {:<8} {:>10.3} ms {:>10} pairs",
+        name,
+        best.as_secs_f64() * 1e3,
+        pairs
+    );
+}
+
+fn main() {
+    let n = env::args()
+        .skip(1)
+        .find(|arg| !arg.starts_with('-'))
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(100_000);
+
+    println!("two_sum, n = {}, best of {} rounds", n, ROUNDS);
+    for (name, nums, target) in distributions(n) {
+        println!("{}:", name);
+        bench("siphash", || {
+            two_sum_with_hasher(&nums, target, All::default(), RandomState::new()).len()
+        });
+        bench("fx", || {
+            two_sum_with_hasher(&nums, target, All::default(), FxBuildHasher::default()).len()
+        });
+        bench("sorting", || two_sum_by_sorting(&nums, target).len());
+    }
+}
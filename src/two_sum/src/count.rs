@@ -0,0 +1,167 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Counting pairs and subarrays by their sum.
+//!
+//! Values are `i32` and sums are taken in `i64`, so they cannot overflow.
+
+use std::collections::HashMap;
+
+/// Number of pairs `i < j` with `nums[i] + nums[j] <= x` in sorted `a`.
+fn count_at_most(a: &[i64], x: i64) -> usize {
+    if a.len() < 2 {
+        return 0;
+    }
+
+    let mut count = 0;
+    let (mut i, mut j) = (0, a.len() - 1);
+    while i < j {
+        if a[i] + a[j] <= x {
+            // a[i] pairs with everything in a[i + 1..=j]
+            count += j - i;
+            i += 1;
+        } else {
+            j -= 1;
+        }
+    }
+    count
+}
+
+/// Number of pairs of indices `i < j` with `lo <= nums[i] + nums[j] <= hi`.
+///
+/// Sorts a copy of `nums` and counts with two pointers, O(n log n).
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::count_pairs_in_range;
+///
+/// // 0 + 1, 0 + 4, 0 + 5 and 1 + 4
+/// assert_eq!(count_pairs_in_range(&[5, 0, 4, 1], 1, 5), 4);
+/// assert_eq!(count_pairs_in_range(&[1, 2], 5, 4), 0);
+/// ```
+pub fn count_pairs_in_range(nums: &[i32], lo: i64, hi: i64) -> usize {
+    if lo > hi {
+        return 0;
+    }
+
+    let mut a: Vec<i64> = nums.iter().map(|&x| x as i64).collect();
+    a.sort_unstable();
+
+    let below = lo.checked_sub(1).map_or(0, |x| count_at_most(&a, x));
+    count_at_most(&a, hi) - below
+}
+
+/// Number of non-empty contiguous subarrays of `nums` summing to `k`.
+///
+/// Counts, for every prefix sum, the earlier prefix sums it differs from by
+/// `k`, O(n) expected time.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::subarray_sum_equals_k;
+///
+/// assert_eq!(subarray_sum_equals_k(&[1, 1, 1], 2), 2);
+/// assert_eq!(subarray_sum_equals_k(&[1, -1, 0], 0), 3);
+/// ```
+pub fn subarray_sum_equals_k(nums: &[i32], k: i64) -> usize {
+    let mut prefixes: HashMap<i64, usize> = HashMap::new();
+    prefixes.insert(0, 1);
+
+    let mut count = 0;
+    let mut sum = 0i64;
+    for &x in nums {
+        sum += x as i64;
+        if let Some(start) = sum.checked_sub(k) {
+            count += prefixes.get(&start).map_or(0, |&n| n);
+        }
+        *prefixes.entry(sum).or_insert(0) += 1;
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::XorShift;
+
+    #[test]
+    fn test_count_pairs_in_range() {
+        assert_eq!(count_pairs_in_range(&[], 0, 0), 0);
+        assert_eq!(count_pairs_in_range(&[3], 0, 10), 0);
+        assert_eq!(count_pairs_in_range(&[3, 3, 3], 6, 6), 3);
+        assert_eq!(
+            count_pairs_in_range(&[i32::MIN, i32::MIN, i32::MAX], i64::MIN, i64::MAX),
+            3
+        );
+        assert_eq!(
+            count_pairs_in_range(&[i32::MIN, i32::MIN, 0], i64::MIN, 2 * i32::MIN as i64),
+            1
+        );
+
+        let mut rng = XorShift(0x48);
+        for _ in 0..300 {
+            let nums: Vec<i32> = (0..rng.below(25))
+                .map(|_| rng.below(21) as i32 - 10)
+                .collect();
+            let lo = rng.below(41) as i64 - 20;
+            let hi = lo + rng.below(12) as i64 - 2;
+
+            let mut expected = 0;
+            for j in 0..nums.len() {
+                for i in 0..j {
+                    let sum = (nums[i] + nums[j]) as i64;
+                    if lo <= sum && sum <= hi {
+                        expected += 1;
+                    }
+                }
+            }
+            assert_eq!(count_pairs_in_range(&nums, lo, hi), expected);
+        }
+    }
+
+    #[test]
+    fn test_subarray_sum_equals_k() {
+        assert_eq!(subarray_sum_equals_k(&[], 0), 0);
+        assert_eq!(subarray_sum_equals_k(&[0, 0, 0], 0), 6);
+        assert_eq!(subarray_sum_equals_k(&[5], i64::MIN), 0);
+        assert_eq!(
+            subarray_sum_equals_k(&[i32::MAX, i32::MAX], 2 * i32::MAX as i64),
+            1
+        );
+
+        let mut rng = XorShift(0x49);
+        for _ in 0..300 {
+            let nums: Vec<i32> = (0..rng.below(30))
+                .map(|_| rng.below(11) as i32 - 5)
+                .collect();
+            let k = rng.below(21) as i64 - 10;
+
+            let mut expected = 0;
+            for i in 0..nums.len() {
+                let mut sum = 0i64;
+                for &x in &nums[i..] {
+                    sum += x as i64;
+                    if sum == k {
+                        expected += 1;
+                    }
+                }
+            }
+            assert_eq!(subarray_sum_equals_k(&nums, k), expected);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A fast hasher for integer keys.
+//!
+//! The default `RandomState` hashes with SipHash, which resists inputs
+//! crafted to collide but costs far more than the lookup itself for small
+//! keys. `FxHasher` mixes each word with a rotate, a xor and a multiply like
+//! the hasher of rustc: several times faster, but with no key, so an
+//! adversary choosing the input can make every key collide. For untrusted
+//! input where speed matters, `two_sum_by_sorting` needs no hashing at all.
+//!
+//! reference:
+//! https://github.com/rust-lang/rustc-hash
+
+use std::hash::{BuildHasherDefault, Hasher};
+
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+/// A non-cryptographic hasher for small keys.
+///
+/// Has no random key, so it offers no protection against inputs crafted to
+/// collide.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use std::collections::HashMap;
+/// use two_sum::FxBuildHasher;
+///
+/// let mut map: HashMap<u32, &str, FxBuildHasher> = HashMap::default();
+/// map.insert(7, "seven");
+/// assert_eq!(map.get(&7), Some(&"seven"));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FxHasher {
+    hash: u64,
+}
+
+/// Creates `FxHasher`s, to be passed where a `BuildHasher` is expected.
+pub type FxBuildHasher = BuildHasherDefault<FxHasher>;
+
+impl FxHasher {
+    #[inline]
+    fn add_to_hash(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for FxHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            self.add_to_hash(u64::from_le_bytes(word));
+        }
+        for &b in chunks.remainder() {
+            self.add_to_hash(b as u64);
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i);
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        self.add_to_hash(i as u64);
+        self.add_to_hash((i >> 64) as u64);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::hash::{BuildHasher, Hash};
+
+    fn hash<T: Hash>(value: T) -> u64 {
+        FxBuildHasher::default().hash_one(value)
+    }
+
+    #[test]
+    fn test_fx_hasher() {
+        assert_eq!(hash(7u32), hash(7u32));
+        assert_ne!(hash(1u64), hash(2u64));
+        assert_ne!(hash(1u128), hash(1u128 << 64));
+        assert_ne!(hash(-1i8), hash(-2i8));
+        assert_ne!(hash("abcdefghi"), hash("abcdefghj"));
+
+        // consecutive keys still spread over the high bits
+        let mut tops: Vec<u64> = (0u64..256).map(|i| hash(i) >> 56).collect();
+        tops.sort();
+        tops.dedup();
+        assert!(tops.len() > 128);
+    }
+}
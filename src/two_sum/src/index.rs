@@ -0,0 +1,376 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Two-sum queries over a multiset of values that changes over time.
+//!
+//! `TwoSumIndex` keeps only the value counts: cheap updates, O(distinct
+//! values) queries. `PairSumIndex` also keeps the number of pairs for every
+//! sum: O(distinct values) updates, O(1) queries.
+
+use std::collections::hash_map::{Entry, RandomState};
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::iter::FromIterator;
+
+use Integer;
+
+/// Some pair of stored values adding up to `target`, smaller value first.
+fn find_pair<T: Integer, S: BuildHasher>(
+    counts: &HashMap<T, usize, S>,
+    target: T,
+) -> Option<(T, T)> {
+    counts.iter().find_map(|(&x, &c)| {
+        let y = target.checked_sub(x)?;
+        let found = if x == y {
+            c >= 2
+        } else {
+            counts.contains_key(&y)
+        };
+        if found {
+            Some((x.min(y), x.max(y)))
+        } else {
+            None
+        }
+    })
+}
+
+fn decrement<T: Integer, S: BuildHasher>(counts: &mut HashMap<T, usize, S>, key: T, by: usize) {
+    if let Entry::Occupied(mut e) = counts.entry(key) {
+        *e.get_mut() -= by;
+        if *e.get() == 0 {
+            e.remove();
+        }
+    }
+}
+
+/// A multiset of values answering "does any pair sum to `target`?".
+///
+/// Updates are O(1), queries O(distinct values). Prefer `PairSumIndex` when
+/// queries outnumber updates.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::TwoSumIndex;
+///
+/// let mut index = TwoSumIndex::new();
+/// index.add(1);
+/// index.add(3);
+/// index.add(5);
+/// assert_eq!(index.find(4), Some((1, 3)));
+/// assert_eq!(index.find(7), None);
+///
+/// index.add(3);
+/// assert_eq!(index.count_pairs(6), 2);
+/// assert!(index.remove(1));
+/// assert_eq!(index.find(4), None);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TwoSumIndex<T: Integer, S = RandomState> {
+    counts: HashMap<T, usize, S>,
+    len: usize,
+}
+
+impl<T: Integer> TwoSumIndex<T> {
+    pub fn new() -> Self {
+        TwoSumIndex::with_hasher(RandomState::new())
+    }
+}
+
+impl<T: Integer, S: BuildHasher> TwoSumIndex<T, S> {
+    /// An empty index hashing the values with `hasher`.
+    pub fn with_hasher(hasher: S) -> Self {
+        TwoSumIndex {
+            counts: HashMap::with_hasher(hasher),
+            len: 0,
+        }
+    }
+
+    /// Number of values stored, counting repeats.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.counts.contains_key(&value)
+    }
+
+    pub fn add(&mut self, value: T) {
+        *self.counts.entry(value).or_insert(0) += 1;
+        self.len += 1;
+    }
+
+    /// Removes one occurrence of `value`, returning whether there was one.
+    pub fn remove(&mut self, value: T) -> bool {
+        if !self.contains(value) {
+            return false;
+        }
+        decrement(&mut self.counts, value, 1);
+        self.len -= 1;
+        true
+    }
+
+    /// Some pair of stored values adding up to `target`, smaller value first.
+    ///
+    /// Which pair is returned when there are several is unspecified.
+    pub fn find(&self, target: T) -> Option<(T, T)> {
+        find_pair(&self.counts, target)
+    }
+
+    /// Number of pairs of stored values adding up to `target`, each repeated
+    /// value counting as a separate element.
+    pub fn count_pairs(&self, target: T) -> usize {
+        self.counts
+            .iter()
+            .map(|(&x, &c)| match target.checked_sub(x) {
+                Some(y) if x == y => c * (c - 1) / 2,
+                Some(y) if x < y => c * self.counts.get(&y).map_or(0, |&d| d),
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+/// A multiset of values with the pair count of every sum kept up to date.
+///
+/// Updates are O(distinct values), `count_pairs` and `contains_sum` are O(1)
+/// and `find` only scans the values when a pair is known to exist. Sums
+/// overflowing `T` are never recorded since no target can match them.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::PairSumIndex;
+///
+/// let mut index: PairSumIndex<i32> = [1, 3, 3, 5].iter().cloned().collect();
+/// assert_eq!(index.count_pairs(6), 2);
+/// assert_eq!(index.count_pairs(8), 2);
+/// assert!(index.contains_sum(4));
+///
+/// index.remove(3);
+/// assert_eq!(index.count_pairs(6), 1);
+/// assert_eq!(index.find(6), Some((1, 5)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PairSumIndex<T: Integer, S = RandomState> {
+    counts: HashMap<T, usize, S>,
+    sums: HashMap<T, usize, S>,
+    len: usize,
+}
+
+impl<T: Integer> PairSumIndex<T> {
+    pub fn new() -> Self {
+        PairSumIndex::with_hasher(RandomState::new())
+    }
+}
+
+impl<T: Integer, S: BuildHasher + Clone> PairSumIndex<T, S> {
+    /// An empty index hashing the values and the sums with `hasher`.
+    pub fn with_hasher(hasher: S) -> Self {
+        PairSumIndex {
+            counts: HashMap::with_hasher(hasher.clone()),
+            sums: HashMap::with_hasher(hasher),
+            len: 0,
+        }
+    }
+
+    /// Number of values stored, counting repeats.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.counts.contains_key(&value)
+    }
+
+    pub fn add(&mut self, value: T) {
+        for (&x, &c) in &self.counts {
+            if let Some(sum) = value.checked_add(x) {
+                *self.sums.entry(sum).or_insert(0) += c;
+            }
+        }
+        *self.counts.entry(value).or_insert(0) += 1;
+        self.len += 1;
+    }
+
+    /// Removes one occurrence of `value`, returning whether there was one.
+    pub fn remove(&mut self, value: T) -> bool {
+        if !self.contains(value) {
+            return false;
+        }
+        decrement(&mut self.counts, value, 1);
+        self.len -= 1;
+
+        for (&x, &c) in &self.counts {
+            if let Some(sum) = value.checked_add(x) {
+                decrement(&mut self.sums, sum, c);
+            }
+        }
+        true
+    }
+
+    /// Whether any pair of stored values adds up to `target`.
+    pub fn contains_sum(&self, target: T) -> bool {
+        self.sums.contains_key(&target)
+    }
+
+    /// Some pair of stored values adding up to `target`, smaller value first.
+    ///
+    /// Which pair is returned when there are several is unspecified.
+    pub fn find(&self, target: T) -> Option<(T, T)> {
+        if self.contains_sum(target) {
+            find_pair(&self.counts, target)
+        } else {
+            None
+        }
+    }
+
+    /// Number of pairs of stored values adding up to `target`, each repeated
+    /// value counting as a separate element.
+    pub fn count_pairs(&self, target: T) -> usize {
+        self.sums.get(&target).map_or(0, |&n| n)
+    }
+}
+
+impl<T: Integer, S: BuildHasher + Default> FromIterator<T> for TwoSumIndex<T, S> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut index = TwoSumIndex::with_hasher(S::default());
+        index.extend(iter);
+        index
+    }
+}
+
+impl<T: Integer, S: BuildHasher> Extend<T> for TwoSumIndex<T, S> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.add(value);
+        }
+    }
+}
+
+impl<T: Integer, S: BuildHasher + Clone + Default> FromIterator<T> for PairSumIndex<T, S> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut index = PairSumIndex::with_hasher(S::default());
+        index.extend(iter);
+        index
+    }
+}
+
+impl<T: Integer, S: BuildHasher + Clone> Extend<T> for PairSumIndex<T, S> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.add(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::XorShift;
+    use FxBuildHasher;
+
+    fn brute_force(values: &[i8], target: i8) -> usize {
+        let mut n = 0;
+        for (j, &y) in values.iter().enumerate() {
+            n += values[..j]
+                .iter()
+                .filter(|&&x| x as i16 + y as i16 == target as i16)
+                .count();
+        }
+        n
+    }
+
+    #[test]
+    fn test_index() {
+        let mut rng = XorShift(0x47);
+        let mut values: Vec<i8> = Vec::new();
+        let mut index = TwoSumIndex::new();
+        let mut sums = PairSumIndex::with_hasher(FxBuildHasher::default());
+
+        for _ in 0..2000 {
+            // small values plus the extremes to exercise overflow
+            let value = match rng.below(10) {
+                0 => i8::MAX,
+                1 => i8::MIN,
+                _ => rng.below(16) as i8 - 8,
+            };
+            if rng.below(3) == 0 {
+                let pos = values.iter().position(|&v| v == value);
+                assert_eq!(index.remove(value), pos.is_some());
+                assert_eq!(sums.remove(value), pos.is_some());
+                if let Some(pos) = pos {
+                    values.swap_remove(pos);
+                }
+            } else {
+                values.push(value);
+                index.add(value);
+                sums.add(value);
+            }
+            assert_eq!(index.len(), values.len());
+            assert_eq!(sums.len(), values.len());
+
+            let target = match rng.below(10) {
+                0 => -1,
+                1 => i8::MIN,
+                _ => rng.below(32) as i8 - 16,
+            };
+            let expected = brute_force(&values, target);
+            assert_eq!(index.count_pairs(target), expected);
+            assert_eq!(sums.count_pairs(target), expected);
+            assert_eq!(sums.contains_sum(target), expected > 0);
+
+            for found in [index.find(target), sums.find(target)].iter() {
+                match *found {
+                    Some((x, y)) => {
+                        assert!(x <= y);
+                        assert_eq!(x.checked_add(y), Some(target));
+                        assert!(values.contains(&x) && values.contains(&y));
+                        if x == y {
+                            assert!(values.iter().filter(|&&v| v == x).count() >= 2);
+                        }
+                    }
+                    None => assert_eq!(expected, 0),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_index_empty() {
+        let mut index: TwoSumIndex<u32> = TwoSumIndex::new();
+        assert!(index.is_empty());
+        assert!(!index.remove(1));
+        assert_eq!(index.find(0), None);
+
+        let mut sums: PairSumIndex<u32> = PairSumIndex::new();
+        assert!(!sums.remove(1));
+        sums.add(2);
+        assert_eq!(sums.find(4), None);
+        sums.add(2);
+        assert_eq!(sums.find(4), Some((2, 2)));
+        sums.remove(2);
+        assert!(!sums.contains_sum(4));
+    }
+}
@@ -0,0 +1,452 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Combinations of k numbers adding up to a target.
+//!
+//! Unlike the generic `two_sum`, values are `i32` here and sums are taken in
+//! `i64`, so they cannot overflow. Differences from the target use checked
+//! arithmetic, so a target outside the reachable range finds nothing.
+
+use std::collections::HashMap;
+
+/// How `k_sum_with` finds the last two numbers of each combination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Two pointers closing in from both ends of the sorted values.
+    TwoPointer,
+    /// Looking up the complement of each distinct value in a hash map.
+    Hash,
+}
+
+/// Unique triples of values summing to `target`, each in ascending order,
+/// listed in ascending order.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::three_sum;
+///
+/// assert_eq!(three_sum(&[-1, 0, 1, 2, -1, -4], 0), vec![[-1, -1, 2], [-1, 0, 1]]);
+/// ```
+pub fn three_sum(nums: &[i32], target: i64) -> Vec<[i32; 3]> {
+    k_sum(nums, 3, target)
+        .into_iter()
+        .map(|c| [c[0], c[1], c[2]])
+        .collect()
+}
+
+/// Unique quadruples of values summing to `target`, each in ascending order,
+/// listed in ascending order.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::four_sum;
+///
+/// assert_eq!(
+///     four_sum(&[1, 0, -1, 0, -2, 2], 0),
+///     vec![[-2, -1, 1, 2], [-2, 0, 0, 2], [-1, 0, 0, 1]]
+/// );
+/// ```
+pub fn four_sum(nums: &[i32], target: i64) -> Vec<[i32; 4]> {
+    k_sum(nums, 4, target)
+        .into_iter()
+        .map(|c| [c[0], c[1], c[2], c[3]])
+        .collect()
+}
+
+/// Unique combinations of `k` values summing to `target`, each in ascending
+/// order, listed in ascending order. Uses `Strategy::TwoPointer`.
+///
+/// Takes O(n^(k - 1)) time for `k >= 2`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::k_sum;
+///
+/// assert_eq!(k_sum(&[5, 1, 3, 2, 4], 3, 9), vec![vec![1, 3, 5], vec![2, 3, 4]]);
+/// ```
+pub fn k_sum(nums: &[i32], k: usize, target: i64) -> Vec<Vec<i32>> {
+    k_sum_with(nums, k, target, Strategy::TwoPointer)
+}
+
+/// `k_sum` with a choice of strategy for the innermost pair.
+pub fn k_sum_with(nums: &[i32], k: usize, target: i64, strategy: Strategy) -> Vec<Vec<i32>> {
+    let mut sorted = nums.to_vec();
+    sorted.sort_unstable();
+
+    let mut result = Vec::new();
+    let mut prefix = Vec::with_capacity(k);
+    k_sum_sorted(&sorted, k, target, strategy, &mut prefix, &mut result);
+    result
+}
+
+fn k_sum_sorted(
+    a: &[i32],
+    k: usize,
+    target: i64,
+    strategy: Strategy,
+    prefix: &mut Vec<i32>,
+    result: &mut Vec<Vec<i32>>,
+) {
+    let n = a.len();
+
+    match k {
+        _ if k > n => {}
+        0 => {
+            if target == 0 {
+                result.push(prefix.clone());
+            }
+        }
+        1 => {
+            if target >= i32::MIN as i64
+                && target <= i32::MAX as i64
+                && a.binary_search(&(target as i32)).is_ok()
+            {
+                let mut c = prefix.clone();
+                c.push(target as i32);
+                result.push(c);
+            }
+        }
+        2 => {
+            let mut emit = |x: i32, y: i32| {
+                let mut c = prefix.clone();
+                c.push(x);
+                c.push(y);
+                result.push(c);
+            };
+            match strategy {
+                Strategy::TwoPointer => pairs_two_pointer(a, target, &mut emit),
+                Strategy::Hash => pairs_hash(a, target, &mut emit),
+            }
+        }
+        _ => {
+            for i in 0..n - k + 1 {
+                if i > 0 && a[i] == a[i - 1] {
+                    continue;
+                }
+
+                let x = a[i] as i64;
+                // the smallest and the largest sums that start with a[i]
+                let min: i64 = x + a[i + 1..i + k].iter().map(|&y| y as i64).sum::<i64>();
+                let max = x + (k as i64 - 1) * a[n - 1] as i64;
+                if min > target {
+                    break;
+                }
+                if max < target {
+                    continue;
+                }
+
+                prefix.push(a[i]);
+                k_sum_sorted(&a[i + 1..], k - 1, target - x, strategy, prefix, result);
+                prefix.pop();
+            }
+        }
+    }
+}
+
+fn pairs_two_pointer<F: FnMut(i32, i32)>(a: &[i32], target: i64, emit: &mut F) {
+    if a.len() < 2 {
+        return;
+    }
+
+    let (mut lo, mut hi) = (0, a.len() - 1);
+    while lo < hi {
+        let sum = a[lo] as i64 + a[hi] as i64;
+        if sum < target {
+            lo += 1;
+        } else if sum > target {
+            hi -= 1;
+        } else {
+            emit(a[lo], a[hi]);
+            // skip the copies of both values
+            while lo < hi && a[lo] == a[lo + 1] {
+                lo += 1;
+            }
+            while lo < hi && a[hi] == a[hi - 1] {
+                hi -= 1;
+            }
+            lo += 1;
+            hi -= 1;
+        }
+    }
+}
+
+fn pairs_hash<F: FnMut(i32, i32)>(a: &[i32], target: i64, emit: &mut F) {
+    let mut counts = HashMap::new();
+    for &x in a {
+        *counts.entry(x).or_insert(0) += 1;
+    }
+
+    // `a` is sorted, so the distinct values come in ascending order
+    for (i, &x) in a.iter().enumerate() {
+        if i > 0 && a[i - 1] == x {
+            continue;
+        }
+
+        let y = match target.checked_sub(x as i64) {
+            Some(y) => y,
+            None => continue,
+        };
+        if y < x as i64 || y > i32::MAX as i64 {
+            continue;
+        }
+        let y = y as i32;
+        match counts.get(&y) {
+            Some(&c) if y > x || c >= 2 => emit(x, y),
+            _ => {}
+        }
+    }
+}
+
+/// All combinations of `k` indices `i1 < i2 < ... < ik` whose values sum to
+/// `target`, listed in ascending order.
+///
+/// Unlike `k_sum` repeated values give distinct combinations, so the result
+/// can be as large as `n choose k`. The last two indices are found through a
+/// map from values to their indices.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::k_sum_indices;
+///
+/// // invoices matching a payment of 100
+/// let invoices = [40, 25, 60, 35, 40];
+/// assert_eq!(
+///     k_sum_indices(&invoices, 3, 100),
+///     vec![vec![0, 1, 3], vec![1, 3, 4]]
+/// );
+/// ```
+pub fn k_sum_indices(nums: &[i32], k: usize, target: i64) -> Vec<Vec<usize>> {
+    let mut positions: HashMap<i32, Vec<usize>> = HashMap::new();
+    for (i, &x) in nums.iter().enumerate() {
+        positions.entry(x).or_default().push(i);
+    }
+
+    let mut result = Vec::new();
+    let mut prefix = Vec::with_capacity(k);
+    k_sum_indices_from(nums, &positions, 0, k, target, &mut prefix, &mut result);
+    result
+}
+
+fn k_sum_indices_from(
+    nums: &[i32],
+    positions: &HashMap<i32, Vec<usize>>,
+    start: usize,
+    k: usize,
+    target: i64,
+    prefix: &mut Vec<usize>,
+    result: &mut Vec<Vec<usize>>,
+) {
+    let n = nums.len();
+
+    if k == 0 {
+        if target == 0 {
+            result.push(prefix.clone());
+        }
+        return;
+    }
+
+    if n - start < k {
+        return;
+    }
+
+    if k == 2 {
+        for (i, &x) in nums.iter().enumerate().skip(start) {
+            let y = match target.checked_sub(x as i64) {
+                Some(y) if y >= i32::MIN as i64 && y <= i32::MAX as i64 => y,
+                _ => continue,
+            };
+            if let Some(ps) = positions.get(&(y as i32)) {
+                // the indices of y after i
+                let lo = ps.partition_point(|&p| p <= i);
+                for &j in &ps[lo..] {
+                    let mut c = prefix.clone();
+                    c.push(i);
+                    c.push(j);
+                    result.push(c);
+                }
+            }
+        }
+        return;
+    }
+
+    for (i, &x) in nums.iter().enumerate().skip(start) {
+        // no k - 1 values of i32 can make up a rest beyond i64
+        let rest = match target.checked_sub(x as i64) {
+            Some(rest) => rest,
+            None => continue,
+        };
+        prefix.push(i);
+        k_sum_indices_from(nums, positions, i + 1, k - 1, rest, prefix, result);
+        prefix.pop();
+    }
+}
+
+/// The sum of three values closest to `target`, the smaller one on a tie.
+/// `None` if there are fewer than three values.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::three_sum_closest;
+///
+/// assert_eq!(three_sum_closest(&[-1, 2, 1, -4], 1), Some(2));
+/// assert_eq!(three_sum_closest(&[1, 2], 1), None);
+/// ```
+pub fn three_sum_closest(nums: &[i32], target: i64) -> Option<i64> {
+    let n = nums.len();
+    if n < 3 {
+        return None;
+    }
+
+    let mut a = nums.to_vec();
+    a.sort_unstable();
+
+    let mut best = a[0] as i64 + a[1] as i64 + a[2] as i64;
+    let closer = |sum: i64, best: i64| {
+        let (d, e) = (sum.abs_diff(target), best.abs_diff(target));
+        d < e || (d == e && sum < best)
+    };
+
+    for i in 0..n - 2 {
+        let (mut lo, mut hi) = (i + 1, n - 1);
+        while lo < hi {
+            let sum = a[i] as i64 + a[lo] as i64 + a[hi] as i64;
+            if closer(sum, best) {
+                best = sum;
+            }
+
+            if sum < target {
+                lo += 1;
+            } else if sum > target {
+                hi -= 1;
+            } else {
+                return Some(sum);
+            }
+        }
+    }
+
+    Some(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::XorShift;
+
+    /// All index combinations of size `k` in lexicographic order.
+    fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+        if k == 0 {
+            return vec![vec![]];
+        }
+        let mut out = Vec::new();
+        for first in 0..n {
+            for rest in combinations(n, k - 1) {
+                if rest.first().is_none_or(|&r| r > first) {
+                    let mut c = vec![first];
+                    c.extend(rest);
+                    out.push(c);
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_k_sum_against_brute_force() {
+        let mut rng = XorShift(0x45);
+
+        for _ in 0..200 {
+            let n = rng.below(9) as usize;
+            let nums: Vec<i32> = (0..n).map(|_| rng.below(11) as i32 - 5).collect();
+            let k = rng.below(5) as usize;
+            let target = rng.below(13) as i64 - 6;
+
+            let indices: Vec<Vec<usize>> = combinations(n, k)
+                .into_iter()
+                .filter(|c| c.iter().map(|&i| nums[i] as i64).sum::<i64>() == target)
+                .collect();
+            assert_eq!(k_sum_indices(&nums, k, target), indices);
+
+            let mut values: Vec<Vec<i32>> = indices
+                .iter()
+                .map(|c| {
+                    let mut v: Vec<i32> = c.iter().map(|&i| nums[i]).collect();
+                    v.sort();
+                    v
+                })
+                .collect();
+            values.sort();
+            values.dedup();
+            assert_eq!(k_sum(&nums, k, target), values);
+            assert_eq!(k_sum_with(&nums, k, target, Strategy::Hash), values);
+        }
+    }
+
+    #[test]
+    fn test_three_and_four_sum() {
+        assert_eq!(three_sum(&[0, 0, 0, 0], 0), vec![[0, 0, 0]]);
+        assert!(three_sum(&[1, 2], 3).is_empty());
+        assert_eq!(four_sum(&[2, 2, 2, 2, 2], 8), vec![[2, 2, 2, 2]]);
+
+        // sums beyond i32
+        let big = [i32::MAX, i32::MAX, i32::MAX, i32::MIN];
+        assert_eq!(three_sum(&big, 3 * i32::MAX as i64), vec![[i32::MAX; 3]]);
+        assert_eq!(
+            k_sum_with(&big, 2, 2 * i32::MAX as i64, Strategy::Hash),
+            vec![vec![i32::MAX; 2]]
+        );
+
+        // targets no sum can reach without overflowing the search
+        for &target in &[i64::MIN, i64::MAX] {
+            assert!(k_sum_indices(&[1, 2, 3], 3, target).is_empty());
+            assert!(k_sum_indices(&[-1, -2, -3], 2, target).is_empty());
+            assert!(k_sum(&[1, 2, 3], 3, target).is_empty());
+            assert!(k_sum_with(&[1, 2], 2, target, Strategy::Hash).is_empty());
+            assert!(k_sum_with(&[-1, -2], 2, target, Strategy::Hash).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_three_sum_closest() {
+        assert_eq!(three_sum_closest(&[0, 0, 0], 1), Some(0));
+        assert_eq!(three_sum_closest(&[1, 1, 1, 0], -100), Some(2));
+        // 3 and 5 are both 1 away from 4
+        assert_eq!(three_sum_closest(&[0, 1, 2, 4], 4), Some(3));
+        // distances beyond i64
+        assert_eq!(three_sum_closest(&[1, 2, 3], i64::MIN), Some(6));
+        assert_eq!(three_sum_closest(&[-1, -2, -3], i64::MAX), Some(-6));
+
+        let mut rng = XorShift(0xc105e);
+        for _ in 0..200 {
+            let n = rng.below(8) as usize + 3;
+            let nums: Vec<i32> = (0..n).map(|_| rng.below(41) as i32 - 20).collect();
+            let target = rng.below(81) as i64 - 40;
+
+            let best = combinations(n, 3)
+                .into_iter()
+                .map(|c| c.iter().map(|&i| nums[i] as i64).sum::<i64>())
+                .min_by_key(|&s| ((s - target).abs(), s));
+            assert_eq!(three_sum_closest(&nums, target), best);
+        }
+    }
+}
@@ -12,28 +12,304 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
 
-pub fn two_sum(nums: &[i32], target: i32) -> Vec<(usize, usize)> {
-    let mut result = Vec::new();
-    let mut map = HashMap::new();
-
-    for i in 0..nums.len() {
-        let diff = target - nums[i];
-        if map.contains_key(&diff) {
-            let idx = map.get(&diff).unwrap();
-            result.push((*idx, i));
-        } else {
-            map.insert(nums[i], i);
+/// Integer types `two_sum` works with.
+pub trait Integer: Copy + Ord + Hash + Default {
+    fn checked_add(self, other: Self) -> Option<Self>;
+
+    fn checked_sub(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! integer_impl {
+    ($($t:ty)*) => {
+        $(
+            impl Integer for $t {
+                #[inline]
+                fn checked_add(self, other: $t) -> Option<$t> {
+                    <$t>::checked_add(self, other)
+                }
+
+                #[inline]
+                fn checked_sub(self, other: $t) -> Option<$t> {
+                    <$t>::checked_sub(self, other)
+                }
+            }
+        )*
+    };
+}
+
+integer_impl!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+/// What to do with the pairs found by `two_sum_with`.
+pub trait Policy {
+    type Output;
+
+    /// Called when `nums[j]` completes a pair with each of the elements at
+    /// `earlier`. Returns `false` to stop the search.
+    fn matched(&mut self, earlier: &[usize], j: usize) -> bool;
+
+    fn finish(self) -> Self::Output;
+}
+
+/// Collects every pair of indices.
+#[derive(Debug, Clone, Default)]
+pub struct All(Vec<(usize, usize)>);
+
+impl Policy for All {
+    type Output = Vec<(usize, usize)>;
+
+    fn matched(&mut self, earlier: &[usize], j: usize) -> bool {
+        self.0.extend(earlier.iter().map(|&i| (i, j)));
+        true
+    }
+
+    fn finish(self) -> Vec<(usize, usize)> {
+        self.0
+    }
+}
+
+/// Stops at the first pair found.
+#[derive(Debug, Clone, Default)]
+pub struct First(Option<(usize, usize)>);
+
+impl Policy for First {
+    type Output = Option<(usize, usize)>;
+
+    fn matched(&mut self, earlier: &[usize], j: usize) -> bool {
+        self.0 = earlier.first().map(|&i| (i, j));
+        self.0.is_none()
+    }
+
+    fn finish(self) -> Option<(usize, usize)> {
+        self.0
+    }
+}
+
+/// Counts the pairs without listing them.
+#[derive(Debug, Clone, Default)]
+pub struct Count(usize);
+
+impl Policy for Count {
+    type Output = usize;
+
+    fn matched(&mut self, earlier: &[usize], _: usize) -> bool {
+        self.0 += earlier.len();
+        true
+    }
+
+    fn finish(self) -> usize {
+        self.0
+    }
+}
+
+/// All pairs of indices `i < j` with `nums[i] + nums[j] == target`, ordered
+/// by `j`, then by `i`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::two_sum;
+///
+/// assert_eq!(two_sum(&[2, 7, 4, 5], 9), vec![(0, 1), (2, 3)]);
+/// assert_eq!(two_sum(&[3, 3, 3], 6), vec![(0, 1), (0, 2), (1, 2)]);
+/// ```
+pub fn two_sum<T: Integer>(nums: &[T], target: T) -> Vec<(usize, usize)> {
+    two_sum_with(nums, target, All::default())
+}
+
+/// `two_sum` with a policy deciding what is kept of the pairs found.
+///
+/// Remembers the indices of every value seen so far in a `HashMap` and looks
+/// up `target - nums[j]` for each `j`. A difference that overflows `T` cannot
+/// be in `nums`, so such elements are simply skipped.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::{two_sum_with, Count, First};
+///
+/// let nums: [u8; 4] = [200, 100, 55, 155];
+/// assert_eq!(two_sum_with(&nums, 255, First::default()), Some((0, 2)));
+/// assert_eq!(two_sum_with(&nums, 255, Count::default()), 2);
+/// ```
+pub fn two_sum_with<T: Integer, P: Policy>(nums: &[T], target: T, policy: P) -> P::Output {
+    two_sum_with_hasher(nums, target, policy, RandomState::new())
+}
+
+/// `two_sum_with` hashing the values with `hasher`.
+///
+/// `FxBuildHasher` is much faster than the default SipHash for integers but
+/// can be made to collide on purpose, see `FxHasher`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::{two_sum_with_hasher, All, FxBuildHasher};
+///
+/// let pairs = two_sum_with_hasher(&[2, 7, 4, 5], 9, All::default(), FxBuildHasher::default());
+/// assert_eq!(pairs, vec![(0, 1), (2, 3)]);
+/// ```
+pub fn two_sum_with_hasher<T: Integer, P: Policy, S: BuildHasher>(
+    nums: &[T],
+    target: T,
+    mut policy: P,
+    hasher: S,
+) -> P::Output {
+    let mut seen: HashMap<T, Vec<usize>, S> = HashMap::with_hasher(hasher);
+
+    for (j, &x) in nums.iter().enumerate() {
+        if let Some(diff) = target.checked_sub(x) {
+            if let Some(earlier) = seen.get(&diff) {
+                if !policy.matched(earlier, j) {
+                    break;
+                }
+            }
         }
+        seen.entry(x).or_default().push(j);
     }
 
-    result
+    policy.finish()
 }
 
+/// The same pairs as `two_sum`, in the same order, found without hashing.
+///
+/// Sorts the indices by value and runs `two_sum_sorted` on the result,
+/// O(n log n + pairs) however the values were chosen.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::two_sum_by_sorting;
+///
+/// assert_eq!(two_sum_by_sorting(&[2, 7, 4, 5], 9), vec![(0, 1), (2, 3)]);
+/// ```
+pub fn two_sum_by_sorting<T: Integer>(nums: &[T], target: T) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..nums.len()).collect();
+    order.sort_by_key(|&i| nums[i]);
+    let sorted: Vec<T> = order.iter().map(|&i| nums[i]).collect();
+
+    let mut pairs: Vec<(usize, usize)> = two_sum_sorted_with(&sorted, target, All::default())
+        .into_iter()
+        .map(|(a, b)| {
+            let (i, j) = (order[a], order[b]);
+            (i.min(j), i.max(j))
+        })
+        .collect();
+    pairs.sort_by_key(|&(i, j)| (j, i));
+    pairs
+}
+
+/// All pairs of indices `i < j` with `nums[i] + nums[j] == target` in a
+/// sorted slice, ordered by `i`, then by `j`.
+///
+/// Uses two pointers closing in from both ends, O(n + pairs) time and no
+/// `HashMap`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::two_sum_sorted;
+///
+/// assert_eq!(two_sum_sorted(&[1, 2, 2, 3, 4], 5), vec![(0, 4), (1, 3), (2, 3)]);
+/// ```
+pub fn two_sum_sorted<T: Integer>(nums: &[T], target: T) -> Vec<(usize, usize)> {
+    let mut pairs = two_sum_sorted_with(nums, target, All::default());
+    pairs.sort();
+    pairs
+}
+
+/// `two_sum_sorted` with a policy deciding what is kept of the pairs found.
+pub fn two_sum_sorted_with<T: Integer, P: Policy>(
+    nums: &[T],
+    target: T,
+    mut policy: P,
+) -> P::Output {
+    debug_assert!(nums.windows(2).all(|w| w[0] <= w[1]), "not sorted");
+
+    if nums.len() < 2 {
+        return policy.finish();
+    }
+
+    let (mut lo, mut hi) = (0, nums.len() - 1);
+    while lo < hi {
+        let (x, y) = (nums[lo], nums[hi]);
+        // an overflowing sum has the sign of its operands
+        let order = match x.checked_add(y) {
+            Some(sum) => sum.cmp(&target),
+            None if y > T::default() => Ordering::Greater,
+            None => Ordering::Less,
+        };
+
+        match order {
+            Ordering::Less => lo += 1,
+            Ordering::Greater => hi -= 1,
+            Ordering::Equal if x == y => {
+                // every pair within nums[lo..=hi]
+                let earlier: Vec<usize> = (lo..hi).collect();
+                for j in lo + 1..hi + 1 {
+                    if !policy.matched(&earlier[..j - lo], j) {
+                        break;
+                    }
+                }
+                break;
+            }
+            Ordering::Equal => {
+                let lo_end = lo + nums[lo..hi].iter().take_while(|&&v| v == x).count();
+                let hi_start = hi + 1
+                    - nums[lo_end..hi + 1]
+                        .iter()
+                        .rev()
+                        .take_while(|&&v| v == y)
+                        .count();
+
+                let earlier: Vec<usize> = (lo..lo_end).collect();
+                let stopped = (hi_start..hi + 1).any(|j| !policy.matched(&earlier, j));
+                if stopped {
+                    break;
+                }
+
+                lo = lo_end;
+                hi = hi_start - 1;
+            }
+        }
+    }
+
+    policy.finish()
+}
+
+mod count;
+mod hash;
+mod index;
+mod k_sum;
+mod subset;
+
+pub use count::{count_pairs_in_range, subarray_sum_equals_k};
+pub use hash::{FxBuildHasher, FxHasher};
+pub use index::{PairSumIndex, TwoSumIndex};
+pub use k_sum::{
+    four_sum, k_sum, k_sum_indices, k_sum_with, three_sum, three_sum_closest, Strategy,
+};
+pub use subset::{
+    subset_sum, subset_sum_dp, subset_sum_meet_in_middle, TooLarge, DP_MAX_RANGE,
+    MEET_IN_MIDDLE_MAX_LEN,
+};
+
+#[cfg(test)]
+mod testing;
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use testing::XorShift;
 
     #[test]
     fn test_two_sum() {
@@ -45,4 +321,82 @@ mod tests {
             vec![(0usize, 1usize), (2usize, 3usize)]
         );
     }
+
+    fn brute_force(nums: &[i64], target: i64) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for j in 0..nums.len() {
+            for i in 0..j {
+                if nums[i] + nums[j] == target {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        pairs
+    }
+
+    #[test]
+    fn test_two_sum_duplicates() {
+        assert_eq!(two_sum(&[3, 3, 3], 6), vec![(0, 1), (0, 2), (1, 2)]);
+        assert_eq!(two_sum_with(&[3, 3, 3], 6, First::default()), Some((0, 1)));
+        assert_eq!(two_sum_with(&[3, 3, 3], 6, Count::default()), 3);
+        assert_eq!(two_sum_with(&[1, 2], 6, First::default()), None);
+
+        let mut rng = XorShift(0x2);
+        for _ in 0..200 {
+            let nums: Vec<i64> = (0..rng.below(30))
+                .map(|_| rng.below(10) as i64 - 3)
+                .collect();
+            let target = rng.below(14) as i64 - 4;
+            let expected = brute_force(&nums, target);
+
+            assert_eq!(two_sum(&nums, target), expected);
+            assert_eq!(
+                two_sum_with_hasher(&nums, target, All::default(), FxBuildHasher::default()),
+                expected
+            );
+            assert_eq!(two_sum_by_sorting(&nums, target), expected);
+            assert_eq!(
+                two_sum_with(&nums, target, Count::default()),
+                expected.len()
+            );
+
+            let mut sorted = nums.clone();
+            sorted.sort();
+            let mut expected = brute_force(&sorted, target);
+            expected.sort();
+            assert_eq!(two_sum_sorted(&sorted, target), expected);
+            assert_eq!(
+                two_sum_sorted_with(&sorted, target, Count::default()),
+                expected.len()
+            );
+            assert_eq!(
+                two_sum_sorted_with(&sorted, target, First::default()).is_some(),
+                !expected.is_empty()
+            );
+        }
+    }
+
+    #[test]
+    fn test_two_sum_overflow() {
+        assert_eq!(two_sum(&[i32::MIN, 1, i32::MAX], -1), vec![(0, 2)]);
+        assert_eq!(two_sum(&[i32::MAX, i32::MAX, -5], i32::MIN), vec![]);
+        assert_eq!(two_sum(&[250u8, 5, 10, 245], 255), vec![(0, 1), (2, 3)]);
+        assert_eq!(two_sum(&[0u8, 255], 0), vec![]);
+        assert_eq!(
+            two_sum_by_sorting(&[i32::MIN, 1, i32::MAX], -1),
+            vec![(0, 2)]
+        );
+        assert_eq!(
+            two_sum_by_sorting(&[250u8, 5, 10, 245], 255),
+            vec![(0, 1), (2, 3)]
+        );
+
+        assert_eq!(
+            two_sum_sorted(&[i8::MIN, -1, 0, i8::MAX], -1),
+            vec![(0, 3), (1, 2)]
+        );
+        assert_eq!(two_sum_sorted(&[100i8, 100, 127], 127), vec![]);
+        assert_eq!(two_sum_sorted(&[-128i8, -100, -28], -128), vec![(1, 2)]);
+        assert_eq!(two_sum_sorted(&[0u64, u64::MAX], u64::MAX), vec![(0, 1)]);
+    }
 }
@@ -0,0 +1,323 @@
+// Copyright 2018 David Li
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Subsets of numbers adding up to a target.
+//!
+//! Values are `i32`, sums are taken in `i64` like in `k_sum`. Every function
+//! returns the ascending indices of one matching subset; the empty subset
+//! matches a target of 0. Inputs too large for a method are refused with
+//! `TooLarge` instead of exhausting memory.
+//!
+//! reference:
+//! https://en.wikipedia.org/wiki/Subset_sum_problem
+
+use std::error::Error;
+use std::fmt;
+
+/// Largest input `subset_sum_meet_in_middle` accepts: each half lists up to
+/// 2^20 subset sums.
+pub const MEET_IN_MIDDLE_MAX_LEN: usize = 40;
+
+/// Largest difference between the largest and the smallest subset sum
+/// `subset_sum_dp` accepts, which keeps its table at 64 MiB.
+pub const DP_MAX_RANGE: u64 = 1 << 24;
+
+const UNREACHED: u32 = u32::MAX;
+
+/// The input is too long or its values too spread out for the method asked
+/// for, or for any method in the case of `subset_sum`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooLarge;
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("input too large for subset sum")
+    }
+}
+
+impl Error for TooLarge {}
+
+/// A subset of `nums` adding up to `target`, if there is one.
+///
+/// Runs whichever of `subset_sum_dp` and `subset_sum_meet_in_middle` is
+/// estimated to be cheaper: the former for small values, the latter for
+/// short inputs. Fails with `TooLarge` if `target` lies between the smallest
+/// and the largest subset sum but neither method accepts `nums`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::{subset_sum, TooLarge};
+///
+/// assert_eq!(subset_sum(&[3, 34, 4, 12, 5, 2], 9), Ok(Some(vec![2, 4])));
+/// assert_eq!(subset_sum(&[3, 34, 4, 12, 5, 2], 30), Ok(None));
+/// assert_eq!(subset_sum(&[-7, 1_000_000_000, 3], -4), Ok(Some(vec![0, 2])));
+///
+/// let far_apart = vec![1_000_000_000; 50];
+/// assert_eq!(subset_sum(&far_apart, 3_000_000_000), Err(TooLarge));
+/// ```
+pub fn subset_sum(nums: &[i32], target: i64) -> Result<Option<Vec<usize>>, TooLarge> {
+    let (neg, pos) = sum_bounds(nums);
+    if target < neg || target > pos {
+        return Ok(None);
+    }
+
+    let range = (pos - neg) as u64;
+    if nums.len() <= MEET_IN_MIDDLE_MAX_LEN {
+        let n = nums.len() as u64;
+        let half = (nums.len() - nums.len() / 2) as u32;
+        let mitm_cost = (1 << half) * (n + 1);
+        if range > DP_MAX_RANGE || mitm_cost < n * (range + 1) {
+            return subset_sum_meet_in_middle(nums, target);
+        }
+    }
+    subset_sum_dp(nums, target)
+}
+
+/// Smallest and largest subset sums.
+fn sum_bounds(nums: &[i32]) -> (i64, i64) {
+    nums.iter().fold((0, 0), |(neg, pos), &x| {
+        if x < 0 {
+            (neg + x as i64, pos)
+        } else {
+            (neg, pos + x as i64)
+        }
+    })
+}
+
+/// A subset of `nums` adding up to `target` by dynamic programming over all
+/// reachable sums.
+///
+/// Takes O(n * r) time and O(r) memory, where r is the difference between
+/// the largest and the smallest subset sum, so it suits many small values.
+/// Fails with `TooLarge` if `target` lies between the smallest and the
+/// largest subset sum and these are more than `DP_MAX_RANGE` apart.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::subset_sum_dp;
+///
+/// assert_eq!(subset_sum_dp(&[5, -3, 8, 2], 4), Ok(Some(vec![0, 1, 3])));
+/// assert_eq!(subset_sum_dp(&[5, -3, 8, 2], 1), Ok(None));
+/// ```
+pub fn subset_sum_dp(nums: &[i32], target: i64) -> Result<Option<Vec<usize>>, TooLarge> {
+    let (neg, pos) = sum_bounds(nums);
+    if target < neg || target > pos {
+        return Ok(None);
+    }
+    // indices are stored as `u32` below UNREACHED to halve the table
+    if (pos - neg) as u64 > DP_MAX_RANGE || nums.len() >= UNREACHED as usize {
+        return Err(TooLarge);
+    }
+
+    // reached_by[s - neg] is the element that first made the sum s reachable
+    let mut reached_by = vec![UNREACHED; (pos - neg) as usize + 1];
+    let zero = (-neg) as usize;
+    reached_by[zero] = nums.len() as u32;
+
+    for (i, &x) in nums.iter().enumerate() {
+        let step = (x as i64).unsigned_abs() as usize;
+        if x > 0 {
+            // downwards, so that sums reached through nums[i] are not reused
+            for s in (step..reached_by.len()).rev() {
+                if reached_by[s] == UNREACHED && reached_by[s - step] != UNREACHED {
+                    reached_by[s] = i as u32;
+                }
+            }
+        } else if x < 0 {
+            for s in 0..reached_by.len() - step {
+                if reached_by[s] == UNREACHED && reached_by[s + step] != UNREACHED {
+                    reached_by[s] = i as u32;
+                }
+            }
+        }
+    }
+
+    let mut s = (target - neg) as usize;
+    if reached_by[s] == UNREACHED {
+        return Ok(None);
+    }
+
+    // every step goes back to a sum that was reached by an earlier element
+    let mut subset = Vec::new();
+    while s != zero {
+        let i = reached_by[s] as usize;
+        subset.push(i);
+        s = (s as i64 - nums[i] as i64) as usize;
+    }
+    subset.reverse();
+    Ok(Some(subset))
+}
+
+/// All subset sums of `nums` with the bitmask of the subset, `offset` being
+/// added to the bit positions.
+fn subset_sums(nums: &[i32], offset: usize) -> Vec<(i64, u64)> {
+    let mut sums = Vec::with_capacity(1 << nums.len());
+    sums.push((0, 0));
+    for (i, &x) in nums.iter().enumerate() {
+        for k in 0..sums.len() {
+            let (sum, mask) = sums[k];
+            sums.push((sum + x as i64, mask | 1 << (i + offset)));
+        }
+    }
+    sums
+}
+
+/// A subset of `nums` adding up to `target` by meeting in the middle.
+///
+/// Lists the subset sums of both halves and binary searches the sorted sums
+/// of the second half for the complement of each sum of the first. Takes
+/// O(2^(n/2) * n) time and O(2^(n/2)) memory whatever the values, which is
+/// practical up to `MEET_IN_MIDDLE_MAX_LEN` elements. Fails with `TooLarge`
+/// if `target` lies between the smallest and the largest subset sum and
+/// `nums` has more elements than that.
+///
+/// # Examples
+///
+/// ```
+/// extern crate two_sum;
+/// use two_sum::subset_sum_meet_in_middle;
+///
+/// let nums = [1 << 30, -(1 << 29), 12_345, 1 << 29, -12_345];
+/// assert_eq!(subset_sum_meet_in_middle(&nums, 1 << 30), Ok(Some(vec![0])));
+/// assert_eq!(subset_sum_meet_in_middle(&nums, 3 << 29), Ok(Some(vec![0, 3])));
+/// assert_eq!(subset_sum_meet_in_middle(&nums, 1), Ok(None));
+/// ```
+pub fn subset_sum_meet_in_middle(
+    nums: &[i32],
+    target: i64,
+) -> Result<Option<Vec<usize>>, TooLarge> {
+    let (neg, pos) = sum_bounds(nums);
+    if target < neg || target > pos {
+        return Ok(None);
+    }
+    if nums.len() > MEET_IN_MIDDLE_MAX_LEN {
+        return Err(TooLarge);
+    }
+
+    let mid = nums.len() / 2;
+    let left = subset_sums(&nums[..mid], 0);
+    let mut right = subset_sums(&nums[mid..], mid);
+    right.sort_unstable();
+
+    let found = left.iter().find_map(|&(sum, mask)| {
+        let rest = target - sum;
+        let k = right.partition_point(|&(s, _)| s < rest);
+        match right.get(k) {
+            Some(&(s, other)) if s == rest => Some(mask | other),
+            _ => None,
+        }
+    });
+
+    Ok(found.map(|mask| (0..nums.len()).filter(|&i| mask >> i & 1 == 1).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::XorShift;
+
+    fn brute_force(nums: &[i32], target: i64) -> bool {
+        (0u64..1 << nums.len()).any(|mask| {
+            let sum: i64 = (0..nums.len())
+                .filter(|&i| mask >> i & 1 == 1)
+                .map(|i| nums[i] as i64)
+                .sum();
+            sum == target
+        })
+    }
+
+    fn check(
+        nums: &[i32],
+        target: i64,
+        subset: Result<Option<Vec<usize>>, TooLarge>,
+        expected: bool,
+    ) {
+        match subset.unwrap() {
+            Some(subset) => {
+                assert!(subset.windows(2).all(|w| w[0] < w[1]));
+                assert!(subset.last().is_none_or(|&i| i < nums.len()));
+                let sum: i64 = subset.iter().map(|&i| nums[i] as i64).sum();
+                assert_eq!(sum, target);
+            }
+            None => assert!(!expected, "missed {:?} {}", nums, target),
+        }
+    }
+
+    #[test]
+    fn test_subset_sum() {
+        let mut rng = XorShift(0x4a);
+        for _ in 0..500 {
+            let nums: Vec<i32> = (0..rng.below(12))
+                .map(|_| rng.below(31) as i32 - 10)
+                .collect();
+            let target = rng.below(81) as i64 - 30;
+            let expected = brute_force(&nums, target);
+
+            check(&nums, target, subset_sum(&nums, target), expected);
+            check(&nums, target, subset_sum_dp(&nums, target), expected);
+            check(
+                &nums,
+                target,
+                subset_sum_meet_in_middle(&nums, target),
+                expected,
+            );
+        }
+    }
+
+    #[test]
+    fn test_subset_sum_edge_cases() {
+        assert_eq!(subset_sum(&[], 0), Ok(Some(vec![])));
+        assert_eq!(subset_sum(&[], 1), Ok(None));
+        assert_eq!(subset_sum_dp(&[0, 0], 0), Ok(Some(vec![])));
+        assert_eq!(subset_sum_meet_in_middle(&[4, -4], 0), Ok(Some(vec![])));
+        assert_eq!(
+            subset_sum(&[i32::MIN, -1], i32::MIN as i64 - 1),
+            Ok(Some(vec![0, 1]))
+        );
+
+        // 40 large values only work by meeting in the middle
+        let nums: Vec<i32> = (0..40).map(|i| (1 << 30) - i * 7919).collect();
+        let target = [3, 17, 22, 39].iter().map(|&i| nums[i] as i64).sum::<i64>();
+        let subset = subset_sum(&nums, target).unwrap().unwrap();
+        let sum: i64 = subset.iter().map(|&i| nums[i] as i64).sum();
+        assert_eq!(sum, target);
+        assert_eq!(subset_sum(&nums, target + 1), Ok(None));
+
+        // targets no subset sum can reach
+        assert_eq!(subset_sum(&[1, 2, 3], i64::MIN), Ok(None));
+        assert_eq!(subset_sum(&[1, 2, 3], i64::MAX), Ok(None));
+        assert_eq!(subset_sum_meet_in_middle(&[-1, 2], i64::MIN), Ok(None));
+        assert_eq!(subset_sum_meet_in_middle(&[-1, 2], i64::MAX), Ok(None));
+
+        // too long for meeting in the middle, too spread for the table
+        let nums: Vec<i32> = (0..70).map(|i| 1_000_000_000 + i).collect();
+        assert_eq!(subset_sum(&nums, -1), Ok(None));
+        assert_eq!(subset_sum(&nums, 5_000_000_000), Err(TooLarge));
+        assert_eq!(subset_sum_dp(&nums, 5_000_000_000), Err(TooLarge));
+        assert_eq!(
+            subset_sum_meet_in_middle(&nums, 5_000_000_000),
+            Err(TooLarge)
+        );
+
+        // too long for meeting in the middle, small enough for the table
+        let nums = vec![3; 50];
+        assert_eq!(subset_sum(&nums, 61), Ok(None));
+        let subset = subset_sum(&nums, 60).unwrap().unwrap();
+        assert_eq!(subset.len(), 20);
+    }
+}